@@ -0,0 +1,71 @@
+//! Build archives whose header only contain extreme values (huge file counts, offsets and lengths near ``u32::MAX``,
+//! empty files...), then call every read function on them. Random bytes rarely hit those values, so this target pick
+//! them from a list instead. Nothing should panic. Run with ``cargo fuzz run extreme_values``.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pmd_cpack::testing::{build_archive, build_raw_header, exercise_read_api, open_archive};
+use pmd_cpack::HeaderVariant;
+
+/// The values the fields of the header are picked from. The length of the archive and values relative to it are added.
+const EXTREME_VALUES: [u32; 10] = [
+    0,
+    1,
+    8,
+    16,
+    0x7FFF_FFFF,
+    0x8000_0000,
+    u32::MAX - 16,
+    u32::MAX - 8,
+    u32::MAX - 1,
+    u32::MAX,
+];
+
+/// The number of bytes following the header, whose content isn't interesting
+const MAX_BODY_LEN: usize = 64;
+
+/// Return the value selected by ``selector``, that may depend on the length of the archive
+fn pick(selector: u8, archive_len: u32) -> u32 {
+    match selector as usize % (EXTREME_VALUES.len() + 3) {
+        n if n < EXTREME_VALUES.len() => EXTREME_VALUES[n],
+        n if n == EXTREME_VALUES.len() => archive_len,
+        n if n == EXTREME_VALUES.len() + 1 => archive_len.wrapping_sub(1),
+        _ => archive_len.wrapping_add(1),
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (settings, selectors) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let variant = if settings & 1 == 0 {
+        HeaderVariant::LittleEndian
+    } else {
+        HeaderVariant::BigEndian
+    };
+    let entry_count = selectors.len() / 2;
+    let body_len = (*settings as usize >> 2) % MAX_BODY_LEN;
+    let archive_len = (16 + entry_count * 8 + body_len) as u32;
+    let entries: Vec<(u32, u32)> = selectors
+        .chunks_exact(2)
+        .map(|pair| (pick(pair[0], archive_len), pick(pair[1], archive_len)))
+        .collect();
+    // the count either match the table of content, or is an extreme value
+    let count = if settings & 2 == 0 {
+        entries.len() as u32
+    } else {
+        pick(*settings >> 2, archive_len)
+    };
+    let mut archive = build_raw_header(variant, count, &entries);
+    archive.resize(archive.len() + body_len, 0xAA);
+    exercise_read_api(&archive);
+
+    // the writer must produce valid archives when most files are empty
+    let files: Vec<&[u8]> = selectors.iter().map(|selector| if selector % 4 == 0 { &b"data"[..] } else { &[] }).collect();
+    let pack = open_archive(&build_archive(&files));
+    assert_eq!(pack.len(), files.len());
+    for (id, file) in files.iter().enumerate() {
+        assert_eq!(&pack.read_file_to_vec(id).unwrap(), file);
+    }
+});
@@ -0,0 +1,11 @@
+//! Parse arbitrary bytes as an archive with every option, then call every read function on it, including with invalid
+//! ids and ranges. Nothing should panic. Run with ``cargo fuzz run read_archive``, or with
+//! ``cargo fuzz run read_archive regressions`` to start from the archives that caused panics in the past.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pmd_cpack::testing::exercise_read_api;
+
+fuzz_target!(|data: &[u8]| {
+    exercise_read_api(data);
+});
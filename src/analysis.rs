@@ -0,0 +1,432 @@
+use crate::dump::RegionKind;
+use crate::hash::{sha256_reader, HASH_CHUNK_LEN};
+use crate::writer::{align_up, Layout};
+use crate::{Alignment, CPack, CPackError, ContainerKind, EntryInfo, LockStrategy, ParseWarning, WriterProfile};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+
+/// The biggest alignment [`CPack::infer_alignment`] will report. Larger values are more likely a coincidence than a convention.
+const MAX_INFERRED_ALIGNMENT: u32 = 0x800;
+
+/// The maximum number of bytes read from the start of a file by [`CPack::entry_entropy`]
+const ENTROPY_SAMPLE_LEN: u64 = 0x10000;
+
+/// The entropy, in bits per byte, above which a file is considered compressed
+const COMPRESSED_ENTROPY_THRESHOLD: f64 = 7.0;
+
+/// The magic of the compressed containers used by the games. Their content is compressed even when their entropy is low.
+const COMPRESSED_MAGICS: [&[u8]; 5] = [b"PKDPX", b"AT3PX", b"AT4PX", b"AT4PN", b"ATUPX"];
+
+/// Sum ``values``, returning [`CPackError::SizeOverflow`] instead of overflowing
+fn checked_sum<I: IntoIterator<Item = u64>>(values: I) -> Result<u64, CPackError> {
+    values
+        .into_iter()
+        .try_fold(0u64, |total, value| total.checked_add(value))
+        .ok_or(CPackError::SizeOverflow)
+}
+
+/// Return the Shannon entropy of ``data``, in bits per byte
+fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    };
+    let mut counts = [0u64; 256];
+    for byte in data {
+        counts[*byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count != 0)
+        .map(|count| {
+            let probability = *count as f64 / len;
+            probability * (1.0 / probability).log2()
+        })
+        .sum()
+}
+
+/// Return the biggest power of two (up to [`MAX_INFERRED_ALIGNMENT`]) ``offset`` is a multiple of
+fn power_of_two_alignment(offset: u64) -> u32 {
+    if offset == 0 {
+        return MAX_INFERRED_ALIGNMENT;
+    };
+    (1u64 << offset.trailing_zeros()).min(MAX_INFERRED_ALIGNMENT as u64) as u32
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Infer the alignment convention used when this archive was created.
+    ///
+    /// The header alignment is deduced from the start of the data, and the file alignment from the offset of every other non-empty file.
+    /// If there isn't enough file to tell, the file alignment is assumed to be the same as the header one.
+    ///
+    /// The result can be turned into a [`crate::WriterProfile`] to repack a file in a way the game should accept.
+    pub fn infer_alignment(&self) -> Alignment {
+        let header = power_of_two_alignment(self.data_start());
+        let entry = self
+            .offset_table
+            .iter()
+            .filter(|index| index.file_lenght != 0 && index.file_offset as u64 != self.data_start())
+            .map(|index| power_of_two_alignment(index.file_offset as u64))
+            .min()
+            .unwrap_or(header);
+        Alignment { header, entry }
+    }
+
+    /// Deduce the settings this archive was written with: its alignment (which determine the header padding),
+    /// its padding byte and its header variant.
+    ///
+    /// The result can be given to [`crate::CPackWriter::new`] to rebuild the archive the same way. If the archive has no padding,
+    /// the default padding byte of [`WriterProfile`] is used.
+    pub fn infer_packer_profile(&self) -> Result<WriterProfile, CPackError> {
+        let default = WriterProfile::default();
+        Ok(WriterProfile {
+            alignment: self.infer_alignment(),
+            padding_byte: self.infer_padding_byte()?.unwrap_or(default.padding_byte),
+            variant: self.variant(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// How much of an archive is padding, as returned by [`CPack::padding_report`]
+pub struct PaddingReport {
+    /// The number of bytes between the end of the header and the first file
+    pub header_padding: u64,
+    /// The slack of every file, as returned by [`CPack::slack`], by id
+    pub entry_slack: Vec<u64>,
+    /// The total number of bytes that are neither the header nor a file
+    pub total_padding: u64,
+    /// The number of padding bytes the archive would have if repacked with its inferred alignment, or None if it can't be repacked
+    pub repacked_padding: Option<u64>,
+    /// The length of the whole archive
+    pub archive_len: u64,
+}
+
+impl PaddingReport {
+    /// Return the number of bytes a repack with the inferred alignment would save
+    pub fn potential_savings(&self) -> u64 {
+        self.repacked_padding
+            .map(|repacked| self.total_padding.saturating_sub(repacked))
+            .unwrap_or(0)
+    }
+
+    /// Return the part of the archive that is padding, between 0 and 1
+    pub fn overhead_ratio(&self) -> f64 {
+        if self.archive_len == 0 {
+            0.0
+        } else {
+            self.total_padding as f64 / self.archive_len as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// How the files are laid out in an archive, as returned by [`CPack::analyze_layout`]
+pub struct LayoutReport {
+    /// The alignment the gaps are computed with, as returned by [`CPack::infer_alignment`]
+    pub alignment: Alignment,
+    /// The unused ranges bigger than what the alignment require, as (offset, length), ordered by offset.
+    /// This include the ones before the first file and after the last one.
+    pub gaps: Vec<(u64, u64)>,
+    /// The id of the non-empty files stored before the previous non-empty file
+    pub out_of_order: Vec<usize>,
+    /// True if the non-empty files are stored by order of id, without gaps nor overlap between them
+    pub contiguous: bool,
+}
+
+impl LayoutReport {
+    /// Return the total number of bytes in gaps
+    pub fn gap_bytes(&self) -> u64 {
+        self.gaps.iter().fold(0u64, |total, (_, len)| total.saturating_add(*len))
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Describe how the files are laid out: the unused space between them and the files not stored by order of id.
+    ///
+    /// A non-contiguous archive would be read faster by the games (especially from a cartridge or a disc) once repacked.
+    pub fn analyze_layout(&self) -> LayoutReport {
+        let alignment = self.infer_alignment();
+        let mut out_of_order = Vec::new();
+        let mut previous_offset = 0;
+        for (id, info) in self.offset_table.iter().enumerate().filter(|(_, info)| info.length() != 0) {
+            if info.offset() < previous_offset {
+                out_of_order.push(id);
+            };
+            previous_offset = info.offset();
+        }
+
+        let mut by_offset: Vec<EntryInfo> = self.offset_table.iter().copied().filter(|info| info.length() != 0).collect();
+        by_offset.sort_by_key(|info| info.offset());
+        let mut gaps = Vec::new();
+        let mut contiguous = out_of_order.is_empty();
+        let mut expected_start = align_up(self.header_len(), alignment.header);
+        for (position, info) in by_offset.iter().enumerate() {
+            if info.offset() > expected_start {
+                gaps.push((expected_start, info.offset() - expected_start));
+                if position != 0 {
+                    contiguous = false;
+                };
+            } else if info.offset() < expected_start {
+                contiguous = false;
+            };
+            expected_start = expected_start.max(align_up(info.end(), alignment.entry));
+        }
+        if self.archive_len() > expected_start {
+            gaps.push((expected_start, self.archive_len() - expected_start));
+        };
+
+        LayoutReport {
+            alignment,
+            gaps,
+            out_of_order,
+            contiguous,
+        }
+    }
+
+    /// Return the entropy of the file with the given id, in bits per byte (between 0 and 8), computed on its first 64 KiB.
+    ///
+    /// An empty file has an entropy of 0.
+    pub fn entry_entropy(&self, id: usize) -> Result<f64, CPackError> {
+        Ok(shannon_entropy(&self.entropy_sample(id)?))
+    }
+
+    /// Guess whether the file with the given id is compressed, without trying to decompress it.
+    ///
+    /// This is the case if it start with the magic of a compressed container of the games (like ``PKDPX``), or if its
+    /// [`CPack::entry_entropy`] is high. This is an heuristic: uncompressed files can be mistaken as compressed, and the opposite.
+    pub fn looks_compressed(&self, id: usize) -> Result<bool, CPackError> {
+        let sample = self.entropy_sample(id)?;
+        Ok(COMPRESSED_MAGICS.iter().any(|magic| sample.starts_with(magic))
+            || shannon_entropy(&sample) >= COMPRESSED_ENTROPY_THRESHOLD)
+    }
+
+    /// Return the groups of files with identical content, as lists of id. Every group contain at least two files,
+    /// and the groups are ordered by their smallest id. Empty files are ignored.
+    ///
+    /// Only files with the same length are hashed, and each one is streamed through SHA-256 rather than loaded in memory.
+    pub fn find_duplicates(&self) -> Result<Vec<Vec<usize>>, CPackError> {
+        let mut by_length: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (id, info) in self.offset_table.iter().enumerate() {
+            if info.length() != 0 {
+                by_length.entry(info.length()).or_default().push(id);
+            };
+        }
+
+        let mut groups = Vec::new();
+        for ids in by_length.into_values().filter(|ids| ids.len() > 1) {
+            let mut by_hash: HashMap<[u8; 32], Vec<usize>> = HashMap::new();
+            for id in ids {
+                by_hash.entry(self.hash_entry(id)?).or_default().push(id);
+            }
+            groups.extend(by_hash.into_values().filter(|ids| ids.len() > 1));
+        }
+        groups.sort_unstable();
+        Ok(groups)
+    }
+
+    /// Return the SHA-256 of the file with the given id, reading it by chunk. Return an error if it doesn't match its checksum.
+    pub(crate) fn hash_entry(&self, id: usize) -> Result<[u8; 32], CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        let mut buffer = vec![0; (info.length() as usize).min(HASH_CHUNK_LEN)];
+        self.hash_entry_with_buffer(id, &mut buffer)
+    }
+
+    fn hash_entry_with_buffer(&self, id: usize, buffer: &mut [u8]) -> Result<[u8; 32], CPackError> {
+        let info = self.offset_table[id];
+        let mut file = self.lock_file()?;
+        file.seek(SeekFrom::Start(info.offset()))?;
+        let hash = sha256_reader(&mut *file, info.length(), buffer)?;
+        self.observer.on_read(info.offset(), info.length());
+        self.verify_hash(id, info.length(), &hash)?;
+        Ok(hash)
+    }
+
+    /// Return the SHA-256 of every file of the archive, in order of id.
+    ///
+    /// The files are read by chunk in the order they are stored in, with a single buffer, so this is fast even for big archives.
+    pub fn hash_all(&self) -> Result<Vec<[u8; 32]>, CPackError> {
+        let mut ids: Vec<usize> = (0..self.len()).collect();
+        ids.sort_by_key(|id| self.offset_table[*id].offset());
+        let largest = self.offset_table.iter().map(EntryInfo::length).max().unwrap_or(0);
+        let mut buffer = vec![0; (largest as usize).min(HASH_CHUNK_LEN)];
+        let mut hashes = vec![[0; 32]; self.len()];
+        for id in ids {
+            hashes[id] = self.hash_entry_with_buffer(id, &mut buffer)?;
+        }
+        Ok(hashes)
+    }
+
+    fn entropy_sample(&self, id: usize) -> Result<Vec<u8>, CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        self.read_entry_range(id, 0, info.length().min(ENTROPY_SAMPLE_LEN))
+    }
+
+    /// Return the number of bytes between the end of the file with the given id and the start of the next file
+    /// (or the end of the archive for the last one), or None if there is no such file.
+    ///
+    /// Empty files have no slack, and overlapping files have a slack of 0.
+    pub fn slack(&self, id: usize) -> Option<u64> {
+        let info = self.entry_info(id)?;
+        if info.length() == 0 {
+            return Some(0);
+        };
+        let next_start = self
+            .offset_table
+            .iter()
+            .enumerate()
+            .filter(|(other_id, other)| *other_id != id && other.length() != 0 && other.offset() >= info.offset())
+            .map(|(_, other)| other.offset())
+            .min()
+            .unwrap_or_else(|| self.archive_len());
+        Some(next_start.saturating_sub(info.end()))
+    }
+
+    /// Return true if the file with the given id share some bytes with another file, like files deduplicated to point
+    /// to the same data. Modifying the content of such a file also modify the other one. Empty files never overlap.
+    pub fn entry_overlaps(&self, id: usize) -> bool {
+        let info = match self.entry_info(id) {
+            Some(info) if info.length() != 0 => info,
+            _ => return false,
+        };
+        self.offset_table.iter().enumerate().any(|(other_id, other)| {
+            other_id != id && other.length() != 0 && other.offset() < info.end() && info.offset() < other.end()
+        })
+    }
+
+    /// Return the value of the first padding byte of the archive, or None if there is no padding at all.
+    ///
+    /// Archives written by the games and by [`crate::CPackWriter`] use the same byte for all their padding.
+    pub fn infer_padding_byte(&self) -> Result<Option<u8>, CPackError> {
+        let start = match self.layout_regions().iter().find(|region| region.kind == RegionKind::Padding) {
+            Some(region) => region.start,
+            None => return Ok(None),
+        };
+        let mut file = self.lock_file()?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut byte = [0];
+        file.read_exact(&mut byte)?;
+        self.observer.on_read(start, 1);
+        Ok(Some(byte[0]))
+    }
+
+    /// Read every padding byte of the archive, returning a [`ParseWarning::InconsistentPadding`] for the first one that
+    /// differ from the previous ones, if any.
+    ///
+    /// The padding is only checked while parsing with [`crate::Strictness::Strict`], as it may be big. It is read by
+    /// small chunks, whatever its length.
+    pub fn check_padding(&self) -> Result<Option<ParseWarning>, CPackError> {
+        let mut file = self.lock_file()?;
+        Ok(self.scan_padding(&mut *file, self.archive_len())?.0)
+    }
+
+    /// Quantify the padding of the whole archive, to decide whether repacking it is worth it
+    pub fn padding_report(&self) -> PaddingReport {
+        let total_padding = self
+            .layout_regions()
+            .iter()
+            .filter(|region| region.kind == RegionKind::Padding)
+            .fold(0u64, |total, region| total.saturating_add(region.end - region.start));
+        let lengths: Vec<u64> = self.offset_table.iter().map(EntryInfo::length).collect();
+        let repacked_padding = Layout::compute(&WriterProfile::from(self.infer_alignment()), &lengths)
+            .ok()
+            .and_then(|layout| checked_sum(std::iter::once(layout.header_padding).chain(layout.paddings)).ok());
+        // same as calling slack for every file, without being quadratic
+        let mut entry_slack = vec![0; self.len()];
+        let mut by_offset: Vec<(usize, EntryInfo)> = self
+            .offset_table
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, info)| info.length() != 0)
+            .collect();
+        by_offset.sort_by_key(|(id, info)| (info.offset(), *id));
+        for (position, (id, info)) in by_offset.iter().enumerate() {
+            let next_start = by_offset
+                .get(position + 1)
+                .map(|(_, next)| next.offset())
+                .unwrap_or_else(|| self.archive_len());
+            entry_slack[*id] = next_start.saturating_sub(info.end());
+        }
+        PaddingReport {
+            header_padding: self.data_start().saturating_sub(self.header_len()),
+            entry_slack,
+            total_padding,
+            repacked_padding,
+            archive_len: self.archive_len(),
+        }
+    }
+
+    /// Return the sum of the length of every file, counting the bytes shared by overlapping files once per file.
+    ///
+    /// Return [`CPackError::SizeOverflow`] rather than overflowing, whatever the table of content contain.
+    pub fn total_payload_len(&self) -> Result<u64, CPackError> {
+        checked_sum(self.offset_table.iter().map(EntryInfo::length))
+    }
+
+    /// Return the number of bytes of the archive that are part of at least one file, so deduplicated data is only counted once
+    pub fn unique_payload_len(&self) -> Result<u64, CPackError> {
+        let mut ranges: Vec<(u64, u64)> = self
+            .offset_table
+            .iter()
+            .filter(|info| info.length() != 0)
+            .map(|info| (info.offset(), info.end()))
+            .collect();
+        ranges.sort_unstable();
+        let mut total = 0u64;
+        let mut covered_until = 0;
+        for (start, end) in ranges {
+            let start = start.max(covered_until);
+            if end > start {
+                total = total.checked_add(end - start).ok_or(CPackError::SizeOverflow)?;
+                covered_until = end;
+            };
+        }
+        Ok(total)
+    }
+
+    /// Return the number of bytes that are neither the header nor part of a file, like padding and trailing data
+    pub fn total_padding_len(&self) -> Result<u64, CPackError> {
+        checked_sum(
+            self.layout_regions()
+                .iter()
+                .filter(|region| region.kind == RegionKind::Padding)
+                .map(|region| region.end - region.start),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A summary of an archive, as returned by [`CPack::stats`]
+pub struct ArchiveStats {
+    /// The number of file in the archive
+    pub entry_count: usize,
+    /// The length of the whole archive
+    pub archive_len: u64,
+    /// The sum of the length of every file, as returned by [`CPack::total_payload_len`]
+    pub payload_len: u64,
+    /// The space lost to padding
+    pub padding: PaddingReport,
+    /// The groups of files with identical content, as returned by [`CPack::find_duplicates`]
+    pub duplicates: Vec<Vec<usize>>,
+    /// The number of file of each kind, according to [`crate::detect_container`]
+    pub kinds: HashMap<ContainerKind, usize>,
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return a summary of this archive: its size, padding, duplicated files and the kind of its files
+    pub fn stats(&self) -> Result<ArchiveStats, CPackError> {
+        let mut kinds = HashMap::new();
+        for node in self.tree_with_depth(0)? {
+            *kinds.entry(node.kind).or_default() += 1;
+        }
+        Ok(ArchiveStats {
+            entry_count: self.len(),
+            archive_len: self.archive_len(),
+            payload_len: self.total_payload_len()?,
+            padding: self.padding_report(),
+            duplicates: self.find_duplicates()?,
+            kinds,
+        })
+    }
+}
@@ -0,0 +1,44 @@
+use crate::{CPack, CPackError, EntryInfo};
+use io_partition::PartitionMutex;
+use std::io::{Read, Seek};
+
+/// A trait shared by the different kind of archive found in pokemon mystery dungeon games (cpack, farc, bin packs...)
+///
+/// It allow to write tools that work with any of those container without caring about the underlying format.
+pub trait GenericArchive {
+    /// A readable handle to a single entry
+    type Entry: Read;
+    /// The error that may happen when accessing an entry
+    type Error;
+
+    /// Return the number of entry in the archive
+    fn len(&self) -> usize;
+
+    /// Return true if the archive contain no entry
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return a readable handle to the entry with the given id
+    fn read_entry(&self, id: usize) -> Result<Self::Entry, Self::Error>;
+
+    /// Return the position of the entry with the given id in the archive, or None if it doesn't exist
+    fn entry_info(&self, id: usize) -> Option<EntryInfo>;
+}
+
+impl<F: Read + Seek> GenericArchive for CPack<F> {
+    type Entry = PartitionMutex<F>;
+    type Error = CPackError;
+
+    fn len(&self) -> usize {
+        CPack::len(self)
+    }
+
+    fn read_entry(&self, id: usize) -> Result<Self::Entry, Self::Error> {
+        self.get_file(id)
+    }
+
+    fn entry_info(&self, id: usize) -> Option<EntryInfo> {
+        CPack::entry_info(self, id)
+    }
+}
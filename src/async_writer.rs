@@ -0,0 +1,113 @@
+use crate::writer::{Layout, PADDING_CHUNK_LEN};
+use crate::{CPackError, WriterProfile};
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use std::fmt;
+use std::io;
+
+/// The content of a file that will be written by [`AsyncCPackWriter`]
+enum AsyncEntry<'a> {
+    Bytes(Vec<u8>),
+    Reader(Box<dyn AsyncRead + Send + Unpin + 'a>, u64),
+}
+
+impl<'a> AsyncEntry<'a> {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Reader(_, len) => *len,
+        }
+    }
+}
+
+impl<'a> fmt::Debug for AsyncEntry<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Self::Reader(_, len) => write!(f, "Reader({} bytes)", len),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// The async equivalent of [`crate::CPackWriter`], reading files from [`AsyncRead`] sources and writing to an [`AsyncWrite`].
+///
+/// The whole layout is computed before writing, so the output doesn't need to be seekable.
+pub struct AsyncCPackWriter<'a> {
+    files: Vec<AsyncEntry<'a>>,
+    profile: WriterProfile,
+}
+
+impl<'a> AsyncCPackWriter<'a> {
+    /// Create an empty [`AsyncCPackWriter`] that will use the given profile
+    pub fn new(profile: WriterProfile) -> Self {
+        Self {
+            files: Vec::new(),
+            profile,
+        }
+    }
+
+    /// add a file to the cpack
+    pub fn push(&mut self, file: Vec<u8>) {
+        self.files.push(AsyncEntry::Bytes(file));
+    }
+
+    /// add a file to the cpack, whose ``len`` bytes will be read from ``reader`` when writing
+    pub fn push_reader<R: AsyncRead + Send + Unpin + 'a>(&mut self, reader: R, len: u64) {
+        self.files.push(AsyncEntry::Reader(Box::new(reader), len));
+    }
+
+    /// Return the number of file that will be written
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Return true if no file were added
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Write the cpack file to ``out``
+    pub async fn write<W: AsyncWrite + Unpin>(self, out: &mut W) -> Result<(), CPackError> {
+        let lengths: Vec<u64> = self.files.iter().map(AsyncEntry::len).collect();
+        let layout = Layout::compute(&self.profile, &lengths)?;
+        let padding_byte = self.profile.padding_byte;
+
+        out.write_all(&layout.header).await?;
+        write_padding(out, padding_byte, layout.header_padding).await?;
+        for (file, padding_len) in self.files.into_iter().zip(layout.paddings) {
+            match file {
+                AsyncEntry::Bytes(bytes) => out.write_all(&bytes).await?,
+                AsyncEntry::Reader(reader, len) => {
+                    let copied = futures_util::io::copy(reader.take(len), out).await?;
+                    if copied != len {
+                        return Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "the source of a file ended before its expected length",
+                        )
+                        .into());
+                    };
+                }
+            };
+            write_padding(out, padding_byte, padding_len).await?;
+        }
+        out.flush().await?;
+        Ok(())
+    }
+}
+
+async fn write_padding<W: AsyncWrite + Unpin>(
+    out: &mut W,
+    padding_byte: u8,
+    mut len: u64,
+) -> Result<(), CPackError> {
+    if len == 0 {
+        return Ok(());
+    };
+    let chunk = vec![padding_byte; len.min(PADDING_CHUNK_LEN) as usize];
+    while len > 0 {
+        let chunk_len = len.min(chunk.len() as u64);
+        out.write_all(&chunk[..chunk_len as usize]).await?;
+        len -= chunk_len;
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Operations over every cpack archive of a directory, like the extracted file system of a ROM.
+//!
+//! [`open_dir`] find and check the archives, and the returned [`Batch`] run operations on all of them in parallel,
+//! each archive being handled by a single thread. Archives are only kept open during an operation, so a directory
+//! with thousands of them doesn't run out of file descriptors.
+
+use crate::{
+    detect_container, ArchiveStats, CPack, CPackError, ContainerKind, ExtractOptions, HeaderVariant, ParseOptions, ParseReport,
+    Strictness,
+};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An archive found by [`open_dir`]
+pub struct BatchArchive {
+    /// The path of the archive
+    pub path: PathBuf,
+    /// The header variant of the archive
+    pub variant: HeaderVariant,
+}
+
+impl BatchArchive {
+    /// Open and parse the archive again
+    pub fn open(&self) -> Result<CPack<BufReader<File>>, CPackError> {
+        let options = ParseOptions {
+            variant: self.variant,
+            ..Default::default()
+        };
+        CPack::new_with_options(BufReader::new(File::open(&self.path)?), options)
+    }
+}
+
+#[derive(Debug)]
+/// Every archive found in a directory by [`open_dir`]
+pub struct Batch {
+    root: PathBuf,
+    archives: Vec<BatchArchive>,
+    failures: Vec<(PathBuf, CPackError)>,
+}
+
+/// Add the path of every file in ``directory`` and its subdirectories to ``files``. Symbolic links aren't followed.
+fn collect_files(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), CPackError> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        };
+    }
+    Ok(())
+}
+
+/// Check that the file at ``path`` is a cpack archive that can be parsed, returning its variant, or None if it doesn't
+/// look like one
+fn check_archive(path: &Path) -> Result<Option<HeaderVariant>, CPackError> {
+    let mut file = BufReader::new(File::open(path)?);
+    let variant = match detect_container(&mut file)? {
+        ContainerKind::CPack(variant) => variant,
+        _ => return Ok(None),
+    };
+    let options = ParseOptions {
+        variant,
+        ..Default::default()
+    };
+    CPack::new_with_options(file, options)?;
+    Ok(Some(variant))
+}
+
+/// Find every cpack archive in ``directory`` and its subdirectories, and check that they can be parsed.
+///
+/// Archives are recognized by their content with [`detect_container`], not by their extension. The files that look like
+/// an archive but can't be parsed are listed by [`Batch::failures`]: they may be corrupted archives, or other files
+/// that happen to start with four zero bytes. An error is only returned if the directory can't be listed.
+pub fn open_dir<P: AsRef<Path>>(directory: P) -> Result<Batch, CPackError> {
+    let root = directory.as_ref().to_path_buf();
+    let mut paths = Vec::new();
+    collect_files(&root, &mut paths)?;
+    paths.sort();
+
+    let checked = run_parallel(&paths, |path| check_archive(path));
+    let mut archives = Vec::new();
+    let mut failures = Vec::new();
+    for (path, result) in paths.into_iter().zip(checked) {
+        match result {
+            Ok(Some(variant)) => archives.push(BatchArchive { path, variant }),
+            Ok(None) => (),
+            Err(err) => failures.push((path, err)),
+        };
+    }
+    Ok(Batch {
+        root,
+        archives,
+        failures,
+    })
+}
+
+/// Call ``operation`` on every item, on one thread per available CPU, returning the results in the order of the items
+fn run_parallel<T, R, O>(items: &[T], operation: O) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    O: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    let nb_workers = thread::available_parallelism()
+        .map(|nb| nb.get())
+        .unwrap_or(1)
+        .min(items.len());
+    thread::scope(|scope| {
+        for _ in 0..nb_workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let item = match items.get(index) {
+                    Some(item) => item,
+                    None => break,
+                };
+                let result = operation(item);
+                results.lock().unwrap_or_else(|poisoned| poisoned.into_inner())[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+impl Batch {
+    /// Return the directory the archives were searched in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Return every archive found, ordered by path
+    pub fn archives(&self) -> &[BatchArchive] {
+        &self.archives
+    }
+
+    /// Return the files that look like an archive but couldn't be opened, with the error, ordered by path
+    pub fn failures(&self) -> &[(PathBuf, CPackError)] {
+        &self.failures
+    }
+
+    /// Return the number of archive found
+    pub fn len(&self) -> usize {
+        self.archives.len()
+    }
+
+    /// Return true if no archive were found
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty()
+    }
+
+    /// Parse every archive again with the given strictness, returning a report of each one in the order of
+    /// [`Batch::archives`]. With [`Strictness::Strict`], this catch inconsistent padding and files overlapping the header.
+    pub fn validate(&self, strictness: Strictness) -> Vec<Result<ParseReport, CPackError>> {
+        run_parallel(&self.archives, |archive| {
+            let options = ParseOptions {
+                variant: archive.variant,
+                strictness,
+                ..Default::default()
+            };
+            let file = BufReader::new(File::open(&archive.path)?);
+            Ok(CPack::parse_with_report(file, options)?.1)
+        })
+    }
+
+    /// Extract every archive into ``directory``. Each archive is extracted with [`CPack::extract_all`] into a
+    /// subdirectory with the same path relative to ``directory`` as the archive relative to [`Batch::root`], like
+    /// ``<directory>/data/font.bin/0.bin``. Return the result for each archive, in the order of [`Batch::archives`].
+    pub fn extract_all<P: AsRef<Path>>(&self, directory: P) -> Vec<Result<(), CPackError>> {
+        self.extract_all_with_options(directory, &ExtractOptions::default())
+    }
+
+    /// Same as [`Batch::extract_all`], using the given [`ExtractOptions`] for every archive
+    pub fn extract_all_with_options<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        options: &ExtractOptions,
+    ) -> Vec<Result<(), CPackError>> {
+        let directory = directory.as_ref();
+        run_parallel(&self.archives, |archive| {
+            let relative = archive.path.strip_prefix(&self.root).unwrap_or(&archive.path);
+            archive.open()?.extract_all_with_options(directory.join(relative), options)
+        })
+    }
+
+    /// Return the [`ArchiveStats`] of every archive, in the order of [`Batch::archives`]
+    pub fn stats(&self) -> Vec<Result<ArchiveStats, CPackError>> {
+        run_parallel(&self.archives, |archive| archive.open()?.stats())
+    }
+}
@@ -0,0 +1,394 @@
+use pmd_cpack::{
+    verify_roundtrip, ArchiveRegistry, CPack, CPackError, CPackWriter, ContainerKind, DynReader, ErrorCode, HeaderVariant, TreeNode,
+    WriterProfile,
+};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage:
+    cpack list [--registry <registry>] <archive>
+        list the id, offset and length of every file of the archive.
+        --registry read a registry of known archives from a file, in the format of pmd_cpack::ArchiveRegistry.
+        if the archive is in it, its description and the name of its known files are also shown.
+        no registry is built in, so without this option the files have no name.
+    cpack tree <archive>
+        list the files of the archive with their type and size, including the files of nested archives.
+    cpack extract <archive> <directory>
+        extract every file of the archive to <id>.bin in the directory.
+        if the directory is -, the files are written to stdout as a tar stream (needs the tar feature).
+    cpack create <output> [<file>...]
+        create an archive containing the given files, in order. without file, an empty archive is created.
+        if the output is -, the archive is written to stdout.
+    cpack grep <archive> <pattern>
+        list the id of the file and the offset of every occurrence of pattern in the archive.
+        a pattern starting with 0x is hexadecimal bytes (like 0x53495230), otherwise it is searched as text.
+    cpack stats <archive>
+        print a summary of the archive: an histogram of the size of the files, the space lost to padding,
+        the duplicated files and the kind of the files.
+    cpack roundtrip <archive>
+        extract every file of the archive, repack them with the settings inferred from the archive,
+        and check that the result is identical to the original.
+
+an archive given as - is read from stdin.
+
+exit status:
+    0 on success, 1 if grep found nothing or roundtrip found a difference, 2 if the command line is invalid.
+    if the command failed, 32 plus the code of the error (see pmd_cpack::ErrorCode), or 2 if it has none.";
+
+/// The exit code when the command ran fine, but found nothing or a difference
+const EXIT_NEGATIVE: i32 = 1;
+/// The exit code when the command line is invalid or the command failed without an [`ErrorCode`]
+const EXIT_ERROR: i32 = 2;
+/// The exit code of a command that failed with an [`ErrorCode`] is this plus the code
+const EXIT_ERROR_CODE_BASE: i32 = 32;
+
+/// The path meaning stdin or stdout, depending on whether it is read or written
+const STD_STREAM: &str = "-";
+
+/// Why a command failed
+struct Failure {
+    message: String,
+    /// The code of the error that caused the failure, if any
+    code: Option<ErrorCode>,
+}
+
+impl Failure {
+    /// Return the exit code of the program for this failure
+    fn exit_code(&self) -> i32 {
+        match self.code {
+            Some(code) => EXIT_ERROR_CODE_BASE + code.code() as i32,
+            None => EXIT_ERROR,
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Self { message, code: None }
+    }
+}
+
+impl From<CPackError> for Failure {
+    fn from(err: CPackError) -> Self {
+        let message = match &err {
+            // the message of the io error say what happened, unlike the generic one of CPackError::IOError
+            CPackError::IOError(err) => err.to_string(),
+            err => err.to_string(),
+        };
+        Self {
+            message,
+            code: Some(err.code()),
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        CPackError::from(err).into()
+    }
+}
+
+/// Return a function turning an error into a [`Failure`] with the same code, with ``context`` before its message
+fn failed<E: Into<CPackError>>(context: impl Display) -> impl Fn(E) -> Failure {
+    move |err| {
+        let failure = Failure::from(err.into());
+        Failure {
+            message: format!("{}: {}", context, failure.message),
+            ..failure
+        }
+    }
+}
+
+/// Open and parse the archive at ``path``. If it is ``-``, stdin is read to the end first, as the archive need to be seeked.
+fn open_archive(path: &str) -> Result<CPack<DynReader>, Failure> {
+    let reader: DynReader = if path == STD_STREAM {
+        let mut content = Vec::new();
+        io::stdin()
+            .lock()
+            .read_to_end(&mut content)
+            .map_err(failed("can't read stdin"))?;
+        Box::new(Cursor::new(content))
+    } else {
+        let file = File::open(path).map_err(failed(format!("can't open {}", path)))?;
+        Box::new(BufReader::new(file))
+    };
+    CPack::new_from_file(reader).map_err(failed(format!("can't parse {}", path)))
+}
+
+/// Decode a pattern given on the command line: hexadecimal bytes if prefixed with ``0x``, the UTF-8 bytes of the text otherwise
+fn parse_pattern(pattern: &str) -> Result<Vec<u8>, String> {
+    let hex = match pattern.strip_prefix("0x") {
+        Some(hex) => hex,
+        None => return Ok(pattern.as_bytes().to_vec()),
+    };
+    if hex.len() % 2 != 0 {
+        return Err(format!("the hexadecimal pattern {:?} has an odd number of digits", pattern));
+    };
+    (0..hex.len())
+        .step_by(2)
+        .map(|start| {
+            hex.get(start..start + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("the pattern {:?} isn't valid hexadecimal", pattern))
+        })
+        .collect()
+}
+
+/// Run ``cpack list``, with the names of the files taken from the registry at ``registry`` if given
+fn list(archive: &str, registry: Option<&str>) -> Result<bool, Failure> {
+    let pack = open_archive(archive)?;
+    let registry = match registry {
+        Some(path) => ArchiveRegistry::open(path).map_err(failed(format!("can't read the registry {}", path)))?,
+        None => ArchiveRegistry::new(),
+    };
+    let known = registry.find(archive, pack.len());
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if let Some(known) = known {
+        writeln!(out, "# {}", known.description)?;
+    };
+    for id in 0..pack.len() {
+        if let Some(info) = pack.entry_info(id) {
+            match known.and_then(|known| known.entry_name(id)) {
+                Some(name) => writeln!(out, "{}\t0x{:x}\t{}\t{}", id, info.offset(), info.length(), name),
+                None => writeln!(out, "{}\t0x{:x}\t{}", id, info.offset(), info.length()),
+            }?;
+        };
+    }
+    Ok(true)
+}
+
+/// Return the name of a kind of container, as displayed by ``cpack tree``
+fn kind_name(kind: ContainerKind) -> &'static str {
+    match kind {
+        ContainerKind::CPack(HeaderVariant::LittleEndian) => "cpack",
+        ContainerKind::CPack(HeaderVariant::BigEndian) => "cpack (big endian)",
+        ContainerKind::Farc => "farc",
+        ContainerKind::Sir0 => "sir0",
+        ContainerKind::Unknown => "data",
+    }
+}
+
+/// Write ``nodes`` and their children, each line prefixed by ``prefix`` and the branches of the tree
+fn write_tree<W: Write>(out: &mut W, nodes: &[TreeNode], prefix: &str) -> io::Result<()> {
+    for (position, node) in nodes.iter().enumerate() {
+        let last = position + 1 == nodes.len();
+        let (branch, continuation) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        writeln!(out, "{}{}{} [{}, {} bytes]", prefix, branch, node.id, kind_name(node.kind), node.length)?;
+        write_tree(out, &node.children, &format!("{}{}", prefix, continuation))?;
+    }
+    Ok(())
+}
+
+/// Run ``cpack tree``
+fn tree(archive: &str) -> Result<bool, Failure> {
+    let pack = open_archive(archive)?;
+    let nodes = pack.tree().map_err(failed(format!("can't read {}", archive)))?;
+    let count: usize = nodes.iter().map(TreeNode::count).sum();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    (|| {
+        writeln!(out, "{}", archive)?;
+        write_tree(&mut out, &nodes, "")?;
+        writeln!(out, "\n{} files", count)
+    })()?;
+    Ok(true)
+}
+
+/// Run ``cpack extract``
+fn extract(archive: &str, directory: &str) -> Result<bool, Failure> {
+    let pack = open_archive(archive)?;
+    if directory == STD_STREAM {
+        extract_to_stdout(&pack)?;
+    } else {
+        pack.extract_all(directory)
+            .map_err(failed(format!("can't extract {} to {}", archive, directory)))?;
+    };
+    Ok(true)
+}
+
+#[cfg(feature = "tar")]
+fn extract_to_stdout<F: Read + Seek>(pack: &CPack<F>) -> Result<(), Failure> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    pack.extract_to_tar(&mut out)
+        .map_err(failed("can't write the tar stream to stdout"))?;
+    Ok(out.flush()?)
+}
+
+#[cfg(not(feature = "tar"))]
+fn extract_to_stdout<F: Read + Seek>(_pack: &CPack<F>) -> Result<(), Failure> {
+    Err("extracting to stdout needs cpack to be built with the tar feature".to_string().into())
+}
+
+/// Run ``cpack create``
+fn create(output: &str, files: &[&str]) -> Result<bool, Failure> {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    for file in files {
+        writer
+            .add(Path::new(file))
+            .map_err(failed(format!("can't add {}", file)))?;
+    }
+    if output == STD_STREAM {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        writer
+            .write(&mut out)
+            .map_err(failed("can't write the archive to stdout"))?;
+        out.flush()?;
+    } else {
+        writer
+            .save_atomic(output)
+            .map_err(failed(format!("can't write {}", output)))?;
+    };
+    Ok(true)
+}
+
+/// Run ``cpack grep``, returning whether at least one occurrence was found
+fn grep(archive: &str, pattern: &str) -> Result<bool, Failure> {
+    let pattern = parse_pattern(pattern)?;
+    let pack = open_archive(archive)?;
+    let occurrences = pack.search(&pattern).map_err(failed(format!("can't search {}", archive)))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for (id, offset) in &occurrences {
+        writeln!(out, "{}\t0x{:x}", id, offset)?;
+    }
+    Ok(!occurrences.is_empty())
+}
+
+/// The length of the longest bar of the histogram of ``cpack stats``
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Format a number of bytes with a binary unit, like ``4 KiB``. Only exact multiples use a bigger unit.
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = "B";
+    for next_unit in UNITS.iter() {
+        if value < 1024 || !value.is_multiple_of(1024) {
+            break;
+        };
+        value /= 1024;
+        unit = next_unit;
+    }
+    format!("{} {}", value, unit)
+}
+
+/// Return the label of the histogram bucket of files whose length has ``bits`` significant bits
+fn bucket_label(bits: u32) -> String {
+    match bits {
+        0 => "empty".to_string(),
+        _ => format!("{} - {}", format_size(1 << (bits - 1)), format_size(1 << bits)),
+    }
+}
+
+/// Run ``cpack stats``
+fn stats(archive: &str) -> Result<bool, Failure> {
+    let pack = open_archive(archive)?;
+    let read_error = failed(format!("can't read {}", archive));
+
+    // bucket by power of two, keyed by the number of significant bits of the length
+    let mut histogram: BTreeMap<u32, usize> = BTreeMap::new();
+    for info in pack.entry_table() {
+        *histogram.entry(64 - info.length().leading_zeros()).or_default() += 1;
+    }
+    let stats = pack.stats().map_err(&read_error)?;
+    let padding = &stats.padding;
+    let duplicates = &stats.duplicates;
+    let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+    for (kind, count) in &stats.kinds {
+        *kinds.entry(kind_name(*kind)).or_default() += count;
+    }
+    let mut compressed = 0;
+    for id in 0..pack.len() {
+        if pack.looks_compressed(id).map_err(&read_error)? {
+            compressed += 1;
+        };
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    (|| {
+        writeln!(out, "{}", archive)?;
+        writeln!(out, "{} files, {} bytes", pack.len(), pack.archive_len())?;
+
+        writeln!(out, "\nfile sizes (the upper bound is excluded):")?;
+        let largest = histogram.values().copied().max().unwrap_or(0);
+        for (bits, count) in &histogram {
+            let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(largest));
+            writeln!(out, "    {:>19} {:>8} {}", bucket_label(*bits), count, bar)?;
+        }
+
+        writeln!(out, "\npadding:")?;
+        writeln!(
+            out,
+            "    {} bytes ({:.1}% of the archive), {} of them after the header",
+            padding.total_padding,
+            padding.overhead_ratio() * 100.0,
+            padding.header_padding
+        )?;
+        if padding.repacked_padding.is_some() {
+            writeln!(out, "    a repack would save {} bytes", padding.potential_savings())?;
+        };
+
+        writeln!(out, "\nduplicates:")?;
+        let redundant: usize = duplicates.iter().map(|group| group.len() - 1).sum();
+        writeln!(out, "    {} files are a copy of another file, in {} groups", redundant, duplicates.len())?;
+
+        writeln!(out, "\ndetected types:")?;
+        for (kind, count) in &kinds {
+            writeln!(out, "    {:>19} {:>8}", kind, count)?;
+        }
+        writeln!(out, "    {} files look compressed", compressed)
+    })()?;
+    Ok(true)
+}
+
+/// Run ``cpack roundtrip``, returning whether the rebuilt archive is identical to the original
+fn roundtrip(archive: &str) -> Result<bool, Failure> {
+    let pack = open_archive(archive)?;
+    let profile = pack
+        .infer_packer_profile()
+        .map_err(failed(format!("can't analyze {}", archive)))?;
+    let report = verify_roundtrip(&pack, profile).map_err(failed(format!("can't rebuild {}", archive)))?;
+    match report.first_difference {
+        None => println!("the rebuilt archive is identical to {}", archive),
+        Some(offset) => println!(
+            "the rebuilt archive differ from {} at offset 0x{:x} (original length: {}, rebuilt length: {})",
+            archive, offset, report.original_len, report.rebuilt_len
+        ),
+    };
+    Ok(report.is_identical())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["list", archive] => list(archive, None),
+        ["list", "--registry", registry, archive] => list(archive, Some(registry)),
+        ["tree", archive] => tree(archive),
+        ["extract", archive, directory] => extract(archive, directory),
+        ["create", output, files @ ..] => create(output, files),
+        ["grep", archive, pattern] => grep(archive, pattern),
+        ["stats", archive] => stats(archive),
+        ["roundtrip", archive] => roundtrip(archive),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(EXIT_ERROR);
+        }
+    };
+    match result {
+        Ok(true) => (),
+        Ok(false) => process::exit(EXIT_NEGATIVE),
+        Err(failure) => {
+            eprintln!("cpack: {}", failure.message);
+            process::exit(failure.exit_code());
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::hash::{sha256, sha256_from_hex, to_hex};
+use crate::{CPack, CPackError, LockStrategy, SaveOptions};
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+/// Return true if ``name`` is the name of an entry of an [`ExtractionCache`]: a SHA-256 in hexadecimal
+fn is_entry_name(name: &OsStr) -> bool {
+    name.to_str().and_then(sha256_from_hex).is_some()
+}
+
+#[derive(Debug, Clone)]
+/// A directory storing the result of processing the files of archives (like decompressing them), keyed by the SHA-256
+/// of their raw content, so the processing isn't done again in later runs.
+///
+/// The processing must only depend on the raw content of the file. Use a separate directory for each kind of processing.
+pub struct ExtractionCache {
+    directory: PathBuf,
+}
+
+impl ExtractionCache {
+    /// Use ``directory`` as the cache, creating it if needed
+    pub fn open<P: Into<PathBuf>>(directory: P) -> Result<Self, CPackError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    /// Return the directory of this cache
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Return the result of ``process`` for the file with the given id of ``pack``, calling it with the raw content of the file
+    /// and storing its result only if it isn't in the cache yet.
+    ///
+    /// If the checksum of the file is known (see [`CPack::set_checksums`]), a cached result is returned without reading the archive.
+    pub fn get_or_insert_with<F, L, P>(&self, pack: &CPack<F, L>, id: usize, process: P) -> Result<Vec<u8>, CPackError>
+    where
+        F: Read + Seek,
+        L: LockStrategy<F>,
+        P: FnOnce(Vec<u8>) -> Result<Vec<u8>, CPackError>,
+    {
+        let (hash, raw) = match pack.checksums.get(&id) {
+            Some((_, hash)) => (*hash, None),
+            None => {
+                let raw = pack.read_file_to_vec(id)?;
+                (sha256(&raw), Some(raw))
+            }
+        };
+        let path = self.directory.join(to_hex(&hash));
+        match fs::read(&path) {
+            Ok(cached) => return Ok(cached),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => return Err(err.into()),
+        };
+        let raw = match raw {
+            Some(raw) => raw,
+            None => pack.read_file_to_vec(id)?,
+        };
+        let processed = process(raw)?;
+        // written atomically, so a crash or a concurrent run never leave a truncated entry in the cache
+        if let Err(err) = crate::save::save_atomic(&path, &SaveOptions::default(), |file| Ok(file.write_all(&processed)?)) {
+            // another run stored the same entry meanwhile, like on Windows where it can't be replaced while being read
+            if !path.is_file() {
+                return Err(err);
+            };
+        };
+        Ok(processed)
+    }
+
+    /// Remove every entry of the cache. The entries being written by other runs and the other files of the directory
+    /// are left untouched.
+    pub fn clear(&self) -> Result<(), CPackError> {
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() && is_entry_name(&entry.file_name()) {
+                match fs::remove_file(entry.path()) {
+                    // removed by another run meanwhile
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                    result => result?,
+                };
+            };
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use crate::hash::HASH_CHUNK_LEN;
+use crate::{CPack, CPackError, LockStrategy};
+use std::io::{Read, Seek};
+
+/// Return true if both archives contain the same files, with the same content and in the same order, whatever their
+/// layout (alignment, padding, header variant...) is.
+///
+/// The files are compared by chunk, so they are never fully loaded in memory, and the comparison stop at the first
+/// difference. Their length is compared first, so archives with different files are usually told apart without reading them.
+pub fn content_eq<F, L, G, M>(a: &CPack<F, L>, b: &CPack<G, M>) -> Result<bool, CPackError>
+where
+    F: Read + Seek,
+    L: LockStrategy<F>,
+    G: Read + Seek,
+    M: LockStrategy<G>,
+{
+    if a.len() != b.len() {
+        return Ok(false);
+    };
+    if a.entry_table().iter().zip(b.entry_table()).any(|(a_info, b_info)| a_info.length() != b_info.length()) {
+        return Ok(false);
+    };
+    // chunks bigger than the read limit of an archive would be refused
+    let chunk_len = [a.read_limit, b.read_limit]
+        .iter()
+        .flatten()
+        .fold(HASH_CHUNK_LEN as u64, |len, limit| len.min(*limit)) as usize;
+    for id in 0..a.len() {
+        let a_chunks = a.entry_chunks(id, chunk_len)?;
+        let b_chunks = b.entry_chunks(id, chunk_len)?;
+        for (a_chunk, b_chunk) in a_chunks.zip(b_chunks) {
+            if a_chunk? != b_chunk? {
+                return Ok(false);
+            };
+        }
+    }
+    Ok(true)
+}
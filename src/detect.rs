@@ -0,0 +1,50 @@
+use crate::{CPackError, HeaderVariant};
+use std::io::{Read, Seek, SeekFrom};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The kind of container recognized by [`detect_container`]
+pub enum ContainerKind {
+    /// A cpack file, with the header variant that seems to be used
+    CPack(HeaderVariant),
+    /// A FARC archive (starting with ``FARC``)
+    Farc,
+    /// A SIR0 blob (starting with ``SIR0``)
+    Sir0,
+    /// Something else
+    Unknown,
+}
+
+/// Guess the kind of container in ``reader`` by looking at its first bytes, without parsing it.
+///
+/// The position of the reader is restored afterward.
+pub fn detect_container<R: Read + Seek>(reader: &mut R) -> Result<ContainerKind, CPackError> {
+    let start = reader.stream_position()?;
+    let mut buffer = [0; 8];
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..])? {
+            0 => break,
+            len => read += len,
+        }
+    }
+    reader.seek(SeekFrom::Start(start))?;
+
+    if read < 4 {
+        return Ok(ContainerKind::Unknown);
+    };
+    Ok(match &buffer[0..4] {
+        b"FARC" => ContainerKind::Farc,
+        b"SIR0" => ContainerKind::Sir0,
+        [0, 0, 0, 0] => {
+            // a count read with the wrong endianness is almost always way bigger
+            let mut count = [0; 4];
+            count.copy_from_slice(&buffer[4..8]);
+            if read == 8 && u32::from_be_bytes(count) < u32::from_le_bytes(count) {
+                ContainerKind::CPack(HeaderVariant::BigEndian)
+            } else {
+                ContainerKind::CPack(HeaderVariant::LittleEndian)
+            }
+        }
+        _ => ContainerKind::Unknown,
+    })
+}
@@ -0,0 +1,61 @@
+use crate::CPackError;
+use miette::{Diagnostic, LabeledSpan};
+use std::fmt::Display;
+
+/// The position of the table of content in a cpack file
+const TABLE_START: usize = 8;
+
+impl Diagnostic for CPackError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(format!("pmd_cpack::{:?}", self.code())))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let help = match self {
+            Self::FourFirstByteNotZero(_, _) => "this is probably not a cpack file",
+            Self::EndOfFileOutOfScope(_, _, _, _) => "the archive may be truncated",
+            Self::EndOfHeaderNotZero(_, _, _) => {
+                "the number of file in the header may be wrong, or this may be another header variant"
+            }
+            Self::InPlaceEditImpossible => "write the modified archive to a new file instead",
+            Self::IndexMismatch(_, _) => "the archive may have changed since it was indexed, parse it again",
+            Self::InvalidEntryPath(_) => "use the form <archive path>#<id> or cpack://<archive path>/<id>",
+            Self::InvalidTarEntry(_) => "name the files of the tar stream <id> or <id>.bin, starting at 0",
+            Self::MissingTarEntry(_) => "the ids of the files must follow each other without gap, starting at 0",
+            Self::SizeOverflow => "the table of content is probably corrupted",
+            Self::ArchiveLocked => "wait for the other program to finish, then try again",
+            Self::ArchiveModified => "open the archive again to edit its current content",
+            Self::UnfilledEntry(_) => "call fill with the handle returned by reserve before writing the archive",
+            Self::ReservedLengthMismatch(_, _, _) => "reserve the exact length of the content the file will be filled with",
+            Self::InvalidExtractedName(_, _) => "entry processors may only change the name of the file, not its directory",
+            Self::ChecksumMismatch(_) => "the archive was modified after the checksums were recorded, or is corrupted",
+            Self::ReadLimitExceeded(_, _) => "read the file by part with get_file, or raise the read limit if the archive is trusted",
+            Self::InvalidRegistry(_) => "each line must be an archive line or the id and name of a file, separated by tabulations",
+            Self::InvalidManifest(_) => "the manifest must be the one written when extracting the archive, with only the files next to it modified",
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+
+    /// Label the offending bytes of the archive. Attach the archive content to the error (for example with
+    /// ``miette::Report::with_source_code``) to display them.
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = match self {
+            Self::FourFirstByteNotZero(_, _) => {
+                LabeledSpan::new(Some("expected 4 zero bytes".to_string()), 0, 4)
+            }
+            Self::EndOfFileOutOfScope(file_id, end, _, _) => LabeledSpan::new(
+                Some(format!("file {} end at {}", file_id, end)),
+                TABLE_START + *file_id as usize * 8,
+                8,
+            ),
+            Self::EndOfHeaderNotZero(end_of_header, _, _) => LabeledSpan::new(
+                Some("expected 8 zero bytes".to_string()),
+                (*end_of_header as usize).saturating_sub(8),
+                8,
+            ),
+            _ => return None,
+        };
+        Some(Box::new(std::iter::once(label)))
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{CPack, CPackError, LockStrategy};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// The maximum number of bytes shown for each region by [`CPack::annotated_dump`]
+const DUMP_PREVIEW_LEN: u64 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What a part of the archive contains
+pub(crate) enum RegionKind {
+    Header,
+    File(usize),
+    /// Anything between the header and the files, or after the files
+    Padding,
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A contiguous part of the archive, as returned by [`CPack::layout_regions`]
+pub(crate) struct Region {
+    pub start: u64,
+    pub end: u64,
+    pub kind: RegionKind,
+}
+
+/// Write a line of up to 16 bytes, as hexadecimal then as ascii
+fn write_dump_line<W: Write>(out: &mut W, offset: u64, bytes: &[u8]) -> Result<(), CPackError> {
+    write!(out, "    {:08x}:", offset)?;
+    for byte in bytes {
+        write!(out, " {:02x}", byte)?;
+    }
+    for _ in bytes.len()..16 {
+        write!(out, "   ")?;
+    }
+    let ascii: String = bytes
+        .iter()
+        .map(|byte| {
+            if byte.is_ascii_graphic() || *byte == b' ' {
+                *byte as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    writeln!(out, "  |{}|", ascii)?;
+    Ok(())
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Split the archive in the header, the files and the padding between them, ordered by offset.
+    /// Empty files are included, and overlapping files are kept as-is.
+    pub(crate) fn layout_regions(&self) -> Vec<Region> {
+        let mut regions = vec![Region {
+            start: 0,
+            end: self.header_len(),
+            kind: RegionKind::Header,
+        }];
+        let mut entries: Vec<_> = self.offset_table.iter().enumerate().collect();
+        entries.sort_by_key(|(id, info)| (info.offset(), *id));
+        let mut position = self.header_len();
+        for (id, info) in entries {
+            if info.offset() > position {
+                regions.push(Region {
+                    start: position,
+                    end: info.offset(),
+                    kind: RegionKind::Padding,
+                });
+            };
+            position = position.max(info.end());
+            regions.push(Region {
+                start: info.offset(),
+                end: info.end(),
+                kind: RegionKind::File(id),
+            });
+        }
+        if self.archive_len() > position {
+            regions.push(Region {
+                start: position,
+                end: self.archive_len(),
+                kind: RegionKind::Padding,
+            });
+        };
+        regions
+    }
+
+    /// Write a human readable description of the archive to ``out``: the header, every file and the padding between them,
+    /// each with its offset, its length and an hexadecimal and ascii preview of its first bytes.
+    pub fn annotated_dump<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        let mut file = self.lock_file()?;
+        for Region { start, end, kind } in self.layout_regions() {
+            let len = end - start;
+            let label = match kind {
+                RegionKind::Header => format!("header ({} files)", self.len()),
+                RegionKind::File(id) => format!("file {}", id),
+                RegionKind::Padding => "padding".to_string(),
+            };
+            writeln!(out, "{:08x}-{:08x} {} ({} bytes)", start, end, label, len)?;
+            file.seek(SeekFrom::Start(start))?;
+            let mut preview = Vec::new();
+            (&mut *file).take(len.min(DUMP_PREVIEW_LEN)).read_to_end(&mut preview)?;
+            self.observer.on_read(start, preview.len() as u64);
+            for (line_nb, line) in preview.chunks(16).enumerate() {
+                write_dump_line(out, start + line_nb as u64 * 16, line)?;
+            }
+            if len > preview.len() as u64 {
+                writeln!(out, "    ... {} more bytes", len - preview.len() as u64)?;
+            };
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{CPack, CPackError, ParseOptions};
+use std::io::{Read, Seek};
+
+/// A reader that can be used as the source of an archive behind a trait object, implemented for every
+/// ``Read + Seek + Send`` type.
+///
+/// [`Read`] and [`Seek`] alone can't be combined in a ``dyn`` type, this trait exist so they can.
+pub trait ReadSeekSend: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeekSend for T {}
+
+/// A boxed reader of any type, so the source of a [`CPack`] can be chosen at runtime (a file, a buffer in memory,
+/// a remote file...) while always having the same type. The archive can still be shared between threads.
+pub type DynReader = Box<dyn ReadSeekSend>;
+
+impl CPack<DynReader> {
+    /// Create a CPack struct from a reader of any type, boxed as a [`DynReader`]
+    pub fn new_dyn<R: ReadSeekSend + 'static>(reader: R) -> Result<Self, CPackError> {
+        Self::new_dyn_with_options(reader, ParseOptions::default())
+    }
+
+    /// Same as [`CPack::new_dyn`], using the given [`ParseOptions`]
+    pub fn new_dyn_with_options<R: ReadSeekSend + 'static>(reader: R, options: ParseOptions) -> Result<Self, CPackError> {
+        Self::new_with_options(Box::new(reader), options)
+    }
+}
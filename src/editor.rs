@@ -0,0 +1,677 @@
+use crate::writer::align_up;
+use crate::{CPack, CPackError, CPackWriter, SaveOptions, WriterProfile};
+use std::borrow::Cow;
+use std::fs::File;
+#[cfg(feature = "file-lock")]
+use crate::{CPackIndex, ParseOptions, Strictness};
+#[cfg(feature = "file-lock")]
+use std::fs::TryLockError;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A writable storage whose length can be changed, needed by [`CPackEditor::commit_in_place_truncating`] to shrink
+/// the archive. Implemented for [`File`] and for an [`io::Cursor`] over a [`Vec`].
+pub trait SetLen {
+    /// Truncate or extend the storage to ``len`` bytes. The position isn't changed.
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+}
+
+impl SetLen for File {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+}
+
+impl SetLen for io::Cursor<Vec<u8>> {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "the length doesn't fit in memory"))?;
+        self.get_mut().resize(len, 0);
+        Ok(())
+    }
+}
+
+/// The function used to change the length of the archive, when it is supported
+type SetLenFn<F> = fn(&mut F, u64) -> io::Result<()>;
+
+#[derive(Debug, Clone)]
+/// The staged state of a single file
+enum StagedEntry {
+    /// The file with this id in the original archive, unchanged
+    Original(usize),
+    /// A file whose content was replaced or added, shared with the operation of the journal that staged it
+    New(Arc<[u8]>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A modification staged in a [`CPackEditor`]. The content of the files is shared with the staged state, so it is
+/// only stored once.
+pub enum EditOp {
+    /// Replace the content of the file with this id
+    Replace { id: usize, data: Arc<[u8]> },
+    /// Add a file at the end of the archive
+    Push { data: Arc<[u8]> },
+    /// Remove the file with this id, shifting the following ones
+    Remove { id: usize },
+}
+
+impl EditOp {
+    fn apply(&self, entries: &mut Vec<StagedEntry>) -> Result<(), CPackError> {
+        match self {
+            Self::Replace { id, data } => {
+                let entry = entries.get_mut(*id).ok_or(CPackError::EntryNotFound(*id))?;
+                *entry = StagedEntry::New(data.clone());
+            }
+            Self::Push { data } => entries.push(StagedEntry::New(data.clone())),
+            Self::Remove { id } => {
+                if *id >= entries.len() {
+                    return Err(CPackError::EntryNotFound(*id));
+                };
+                entries.remove(*id);
+            }
+        };
+        Ok(())
+    }
+}
+
+impl fmt::Display for EditOp {
+    /// A single line summary of the operation, as used by [`CPackEditor::write_changelog`]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Replace { id, data } => write!(f, "replace {} {}", id, data.len()),
+            Self::Push { data } => write!(f, "push {}", data.len()),
+            Self::Remove { id } => write!(f, "remove {}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// How a file of the edited archive differ from the original archive, as returned by [`CPackEditor::diff`]
+pub enum EntryChange {
+    /// The file is the one with the same id in the original archive
+    Unchanged,
+    /// The file is the one with this id in the original archive, whose id changed as previous files were removed
+    Moved(usize),
+    /// The file replace the one with this id in the original archive
+    Replaced(usize),
+    /// The file was added
+    Added,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The difference between an edited archive and the original one, as returned by [`CPackEditor::diff`]
+pub struct EditDiff {
+    /// The change of every file of the edited archive, by id
+    pub entries: Vec<EntryChange>,
+    /// The id of the files of the original archive that were removed
+    pub removed: Vec<usize>,
+}
+
+impl EditDiff {
+    /// Return true if the edited archive has the same files as the original one
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.entries.iter().all(|change| *change == EntryChange::Unchanged)
+    }
+}
+
+#[derive(Debug)]
+/// Stage modifications to a [`CPack`] in memory, then apply them all at once.
+///
+/// Nothing is written until [`CPackEditor::commit`] or [`CPackEditor::commit_in_place`] is called, and both check
+/// everything they can before writing, so an invalid edit doesn't modify anything. [`CPackEditor::commit_in_place`]
+/// still modify the archive in several writes, so an IO error or a crash in the middle of them can leave it half-modified:
+/// use [`CPackEditor::save`], that replace the archive atomically, or [`CPackEditor::commit_in_place_with_backup`]
+/// when this matters.
+///
+/// Every staged modification is kept in a journal, allowing to [`CPackEditor::undo`] and [`CPackEditor::redo`] them.
+///
+/// This is the single entry point for tools like GUI editors: [`CPackEditor::open`] an archive, [`CPackEditor::read`]
+/// its files as they are staged, stage changes, show them with [`CPackEditor::diff`], then [`CPackEditor::save`] it.
+/// The unmodified files are copied from the original archive when saving, without being loaded in memory, and the
+/// layout of the original archive is kept by inferring its [`WriterProfile`].
+pub struct CPackEditor<F: Read + Seek> {
+    pack: CPack<F>,
+    entries: Vec<StagedEntry>,
+    journal: Vec<EditOp>,
+    undone: Vec<EditOp>,
+    /// The path the archive was opened from with [`CPackEditor::open`]
+    path: Option<PathBuf>,
+    /// If true, the archive was opened with [`CPackEditor::open_read_only`], and can't be modified in place
+    read_only: bool,
+}
+
+impl<F: Read + Seek> CPackEditor<F> {
+    /// Start editing the given archive
+    pub fn new(pack: CPack<F>) -> Self {
+        let entries = (0..pack.len()).map(StagedEntry::Original).collect();
+        Self {
+            pack,
+            entries,
+            journal: Vec::new(),
+            undone: Vec::new(),
+            path: None,
+            read_only: false,
+        }
+    }
+
+    /// Return the archive being edited, as it was before any staged modification
+    pub fn pack(&self) -> &CPack<F> {
+        &self.pack
+    }
+
+    /// Stop editing, discarding any staged modification
+    pub fn into_pack(self) -> CPack<F> {
+        self.pack
+    }
+
+    /// Return the number of file the archive will have once the modifications are applied
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return true if the archive will be empty once the modifications are applied
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the path the archive was opened from, if it was opened with [`CPackEditor::open`] or
+    /// [`CPackEditor::open_read_only`]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Return the content of the file with the given id, with the staged modifications applied
+    pub fn read(&self, id: usize) -> Result<Cow<'_, [u8]>, CPackError> {
+        match self.entries.get(id).ok_or(CPackError::EntryNotFound(id))? {
+            StagedEntry::Original(original) => Ok(Cow::Owned(self.pack.read_file_to_vec(*original)?)),
+            StagedEntry::New(data) => Ok(Cow::Borrowed(&data[..])),
+        }
+    }
+
+    /// Return how the files with the staged modifications applied differ from the ones of the original archive.
+    ///
+    /// A replaced file is reported as replaced even if it was replaced with the same content.
+    pub fn diff(&self) -> EditDiff {
+        // follow where each file of the original archive went, None being the added files
+        let mut origins: Vec<Option<usize>> = (0..self.pack.len()).map(Some).collect();
+        let mut replaced = vec![false; self.pack.len()];
+        for op in &self.journal {
+            match op {
+                EditOp::Replace { id, .. } => {
+                    if let Some(Some(original)) = origins.get(*id) {
+                        replaced[*original] = true;
+                    };
+                }
+                EditOp::Push { .. } => origins.push(None),
+                EditOp::Remove { id } => {
+                    origins.remove(*id);
+                }
+            };
+        }
+        let mut kept = vec![false; self.pack.len()];
+        let entries = origins
+            .iter()
+            .enumerate()
+            .map(|(id, origin)| match origin {
+                Some(original) => {
+                    kept[*original] = true;
+                    if replaced[*original] {
+                        EntryChange::Replaced(*original)
+                    } else if *original == id {
+                        EntryChange::Unchanged
+                    } else {
+                        EntryChange::Moved(*original)
+                    }
+                }
+                None => EntryChange::Added,
+            })
+            .collect();
+        EditDiff {
+            entries,
+            removed: (0..self.pack.len()).filter(|original| !kept[*original]).collect(),
+        }
+    }
+
+    /// Return true if there are staged modifications
+    pub fn has_changes(&self) -> bool {
+        self.entries.len() != self.pack.len()
+            || self
+                .entries
+                .iter()
+                .enumerate()
+                .any(|(id, entry)| !matches!(entry, StagedEntry::Original(original) if *original == id))
+    }
+
+    /// Stage an operation, recording it in the journal. This clear the operations that could be redone.
+    pub fn apply(&mut self, op: EditOp) -> Result<(), CPackError> {
+        op.apply(&mut self.entries)?;
+        self.journal.push(op);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Replace the content of the file with the given id
+    pub fn replace(&mut self, id: usize, data: Vec<u8>) -> Result<(), CPackError> {
+        self.apply(EditOp::Replace { id, data: data.into() })
+    }
+
+    /// Add a file at the end of the archive, returning its id
+    pub fn push(&mut self, data: Vec<u8>) -> usize {
+        // pushing can't fail
+        let _ = self.apply(EditOp::Push { data: data.into() });
+        self.entries.len() - 1
+    }
+
+    /// Remove the file with the given id. The id of the following files are decreased by one.
+    pub fn remove(&mut self, id: usize) -> Result<(), CPackError> {
+        self.apply(EditOp::Remove { id })
+    }
+
+    /// Cancel the last staged operation. Return false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.journal.pop() {
+            Some(op) => {
+                self.undone.push(op);
+                self.replay();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stage again the last undone operation. Return false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            Some(op) => {
+                // the operation was valid in this exact state before being undone
+                let _ = op.apply(&mut self.entries);
+                self.journal.push(op);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the staged operations, in the order they were applied
+    pub fn changelog(&self) -> &[EditOp] {
+        &self.journal
+    }
+
+    /// Write a summary of the staged operations to ``out``, one per line
+    pub fn write_changelog<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        for op in &self.journal {
+            writeln!(out, "{}", op)?;
+        }
+        Ok(())
+    }
+
+    /// Forget every staged modification. This can't be undone.
+    pub fn discard(&mut self) {
+        self.journal.clear();
+        self.undone.clear();
+        self.replay();
+    }
+
+    /// Rebuild the staged state from the original archive and the journal
+    fn replay(&mut self) {
+        self.entries = (0..self.pack.len()).map(StagedEntry::Original).collect();
+        for op in &self.journal {
+            // every operation in the journal was valid when it was recorded
+            let _ = op.apply(&mut self.entries);
+        }
+    }
+
+    /// Write the modified archive to ``out``, using the given profile. The original archive is left untouched.
+    pub fn commit<W: Write>(&self, out: &mut W, profile: WriterProfile) -> Result<(), CPackError> {
+        let mut writer = CPackWriter::new(profile);
+        for entry in &self.entries {
+            match entry {
+                StagedEntry::Original(id) => writer.add_raw_from(&self.pack, *id)?,
+                StagedEntry::New(data) => writer.add(&data[..])?,
+            }
+        }
+        writer.write(out)
+    }
+
+    /// Write the modified archive to ``path`` through a temporary file that is then renamed, so ``path`` is never left truncated.
+    ///
+    /// ``path`` may be the file being edited. Note that on Windows, the rename will fail if that file is opened without sharing deletion,
+    /// use [`crate::open_shared`] to open it.
+    pub fn save_atomic<P: AsRef<Path>>(&self, path: P, profile: WriterProfile) -> Result<(), CPackError> {
+        self.save_atomic_with_options(path, profile, &SaveOptions::default())
+    }
+
+    /// Same as [`CPackEditor::save_atomic`], using the given [`SaveOptions`]
+    pub fn save_atomic_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        profile: WriterProfile,
+        options: &SaveOptions,
+    ) -> Result<(), CPackError> {
+        crate::save::save_atomic(path, options, |file| self.commit(file, profile))
+    }
+
+    /// Return true if the staged modifications can be applied by [`CPackEditor::commit_in_place`].
+    ///
+    /// This is the case when no file were removed or reordered, every replaced file doesn't grow and doesn't share
+    /// its data with another file (see [`CPack::entry_overlaps`]), and the files added at the end fit in the padding
+    /// between the table of content and the first file. It is never the case for an archive opened with
+    /// [`CPackEditor::open_read_only`].
+    pub fn can_commit_in_place(&self) -> bool {
+        self.entries.len() >= self.pack.len() && self.can_write_in_place()
+    }
+
+    /// Return true if the staged modifications can be applied by [`CPackEditor::commit_in_place_truncating`].
+    ///
+    /// This is the case when [`CPackEditor::can_commit_in_place`] is true, or when only the last files were removed,
+    /// with the other files being kept or replaced following the same rules.
+    pub fn can_commit_in_place_truncating(&self) -> bool {
+        self.can_write_in_place()
+    }
+
+    /// Check the staged modifications that can be written in place, ignoring whether files were removed from the end
+    fn can_write_in_place(&self) -> bool {
+        let new_header_len = 8 + self.entries.len() as u64 * 8 + 8;
+        !self.read_only
+            && (self.entries.len() <= self.pack.len() || new_header_len <= self.pack.data_start())
+            && self.entries.iter().enumerate().all(|(id, entry)| match entry {
+                StagedEntry::Original(original) => *original == id,
+                StagedEntry::New(data) => self
+                    .pack
+                    .entry_info(id)
+                    .map(|info| data.len() as u64 <= info.length() && !self.pack.entry_overlaps(id))
+                    .unwrap_or(true),
+            })
+    }
+}
+
+impl<F: Read + Seek + Write> CPackEditor<F> {
+    /// Apply the staged modifications directly in the original archive, overwriting the replaced files.
+    ///
+    /// Only the replaced files are written. When one shrink, its length is updated in the table of content and the freed bytes
+    /// are filled with the padding byte of the archive, so the position of the other files doesn't change.
+    ///
+    /// The added files are written at the end of the archive, extending it (this work with a [`File`] or an
+    /// [`io::Cursor`] over a [`Vec`]), then only their entries in the table of content and the number of file are written.
+    ///
+    /// Return an error without writing anything if [`CPackEditor::can_commit_in_place`] is false. If writing fail, the
+    /// modifications stay staged, so the commit can be tried again.
+    pub fn commit_in_place(&mut self) -> Result<(), CPackError> {
+        if !self.can_commit_in_place() {
+            return Err(CPackError::InPlaceEditImpossible);
+        };
+        self.write_in_place(None)
+    }
+
+    /// Same as [`CPackEditor::commit_in_place`], but first copy the whole original archive to ``backup``.
+    ///
+    /// Nothing is modified if the backup fail.
+    pub fn commit_in_place_with_backup<P: AsRef<Path>>(&mut self, backup: P) -> Result<(), CPackError> {
+        if !self.can_commit_in_place() {
+            return Err(CPackError::InPlaceEditImpossible);
+        };
+        {
+            let mut file = self.pack.lock_file()?;
+            file.seek(SeekFrom::Start(0))?;
+            let mut backup_file = File::create(backup)?;
+            io::copy(&mut *file, &mut backup_file)?;
+            backup_file.sync_all()?;
+        }
+        self.write_in_place(None)
+    }
+
+    /// Return the length of the archive once the files after the last staged one are removed: the end of the last
+    /// remaining file with its alignment padding, but never less than the end of the new header, as the remaining
+    /// files may be empty and point inside of it
+    fn truncated_len(&self, entry_alignment: u32) -> Result<u64, CPackError> {
+        let new_header_len = 8 + self.entries.len() as u64 * 8 + 8;
+        let mut data_end = None;
+        for (id, entry) in self.entries.iter().enumerate() {
+            let info = self.pack.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+            let length = match entry {
+                StagedEntry::Original(_) => info.length(),
+                StagedEntry::New(data) => data.len() as u64,
+            };
+            data_end = data_end.max(Some(info.offset() + length));
+        }
+        Ok(match data_end {
+            Some(end) => align_up(end, entry_alignment).min(self.pack.archive_len()).max(new_header_len),
+            None => new_header_len,
+        })
+    }
+
+    /// Write the staged modifications in the archive. If files were removed from the end, the table of content is
+    /// shortened and the archive is truncated with ``set_len``, that must then be given.
+    fn write_in_place(&mut self, set_len: Option<SetLenFn<F>>) -> Result<(), CPackError> {
+        let padding_byte = self
+            .pack
+            .infer_padding_byte()?
+            .unwrap_or_else(|| WriterProfile::default().padding_byte);
+        let variant = self.pack.variant();
+        let original_len = self.pack.len();
+        let entry_alignment = self.pack.infer_alignment().entry;
+        let truncation = match set_len {
+            Some(set_len) if self.entries.len() < original_len => {
+                Some((set_len, self.truncated_len(entry_alignment)?))
+            }
+            _ => None,
+        };
+
+        // the position of the added files, checked before anything is written
+        let mut added = Vec::new();
+        let mut position = self.pack.archive_len();
+        for (id, entry) in self.entries.iter().enumerate().skip(original_len) {
+            if let StagedEntry::New(data) = entry {
+                let start = align_up(position, entry_alignment);
+                let end = start + data.len() as u64;
+                let padded_end = align_up(end, entry_alignment);
+                if padded_end > u32::MAX as u64 {
+                    return Err(CPackError::OffsetOverflow(padded_end));
+                };
+                added.push((id, position, start));
+                position = padded_end;
+            };
+        }
+
+        let mut file = self.pack.lock_file()?;
+        let mut replaced = Vec::new();
+        let mut new_lengths = Vec::new();
+        for (id, entry) in self.entries.iter().enumerate().take(original_len) {
+            if let StagedEntry::New(data) = entry {
+                let info = self
+                    .pack
+                    .entry_info(id)
+                    .ok_or(CPackError::EntryNotFound(id))?;
+                file.seek(SeekFrom::Start(info.offset()))?;
+                file.write_all(data)?;
+                let new_length = data.len() as u64;
+                if new_length != info.length() {
+                    io::copy(
+                        &mut io::repeat(padding_byte).take(info.length() - new_length),
+                        &mut *file,
+                    )?;
+                    // the length field of the file in the table of content
+                    file.seek(SeekFrom::Start(8 + id as u64 * 8 + 4))?;
+                    file.write_all(&variant.u32_to_bytes(new_length as u32))?;
+                    new_lengths.push((id, new_length as u32));
+                };
+                replaced.push(id);
+            }
+        }
+
+        // the data of the added files is written before the table of content, so the archive stay valid if it fail
+        let mut added_entries = Vec::new();
+        for (id, previous_end, start) in added {
+            let data = match &self.entries[id] {
+                StagedEntry::New(data) => data,
+                StagedEntry::Original(_) => continue,
+            };
+            let end = start + data.len() as u64;
+            file.seek(SeekFrom::Start(previous_end))?;
+            io::copy(&mut io::repeat(padding_byte).take(start - previous_end), &mut *file)?;
+            file.write_all(data)?;
+            io::copy(&mut io::repeat(padding_byte).take(align_up(end, entry_alignment) - end), &mut *file)?;
+            added_entries.extend_from_slice(&variant.u32_to_bytes(start as u32));
+            added_entries.extend_from_slice(&variant.u32_to_bytes(data.len() as u32));
+        }
+        if !added_entries.is_empty() {
+            file.seek(SeekFrom::Start(8 + original_len as u64 * 8))?;
+            file.write_all(&added_entries)?;
+            file.write_all(&[0; 8])?;
+            file.seek(SeekFrom::Start(4))?;
+            file.write_all(&variant.u32_to_bytes(self.entries.len() as u32))?;
+        };
+        // the removed files are cut from the table of content first, so the archive stay valid if the truncation fail
+        if let Some((set_len, new_len)) = truncation {
+            let new_header_len = 8 + self.entries.len() as u64 * 8 + 8;
+            let old_header_len = self.pack.header_len();
+            file.seek(SeekFrom::Start(new_header_len - 8))?;
+            file.write_all(&[0; 8])?;
+            // the end of the old table of content become padding
+            let padding_end = old_header_len.min(new_len).max(new_header_len);
+            io::copy(&mut io::repeat(padding_byte).take(padding_end - new_header_len), &mut *file)?;
+            file.seek(SeekFrom::Start(4))?;
+            file.write_all(&variant.u32_to_bytes(self.entries.len() as u32))?;
+            file.flush()?;
+            set_len(&mut file, new_len)?;
+        };
+        file.flush()?;
+        drop(file);
+        // the editor is only updated once everything was written, so the modifications are still staged on failure
+        for id in replaced {
+            self.entries[id] = StagedEntry::Original(id);
+        }
+        for (id, new_length) in new_lengths {
+            self.pack.offset_table[id].file_lenght = new_length;
+        }
+        if !added_entries.is_empty() || truncation.is_some() {
+            self.pack.reload()?;
+            self.entries = (0..self.pack.len()).map(StagedEntry::Original).collect();
+        };
+        // the original archive now contain the modifications
+        self.journal.clear();
+        self.undone.clear();
+        Ok(())
+    }
+}
+
+impl<F: Read + Seek + Write + SetLen> CPackEditor<F> {
+    /// Same as [`CPackEditor::commit_in_place`], but the last files of the archive may also have been removed.
+    ///
+    /// In this case, only the number of file and the end of the table of content are rewritten, then the archive is
+    /// truncated after the last remaining file and its padding. This avoid rewriting the whole archive to drop some
+    /// files at its end, like debug files. Data after the last file that wasn't padding is also dropped.
+    ///
+    /// Return an error without writing anything if [`CPackEditor::can_commit_in_place_truncating`] is false.
+    pub fn commit_in_place_truncating(&mut self) -> Result<(), CPackError> {
+        if !self.can_commit_in_place_truncating() {
+            return Err(CPackError::InPlaceEditImpossible);
+        };
+        self.write_in_place(Some(<F as SetLen>::set_len))
+    }
+}
+
+#[cfg(any(unix, windows))]
+impl CPackEditor<File> {
+    /// Open and parse the archive at ``path`` for editing. It is opened for reading and writing, so the modifications
+    /// can also be applied with [`CPackEditor::commit_in_place`], with the share modes of [`crate::open_shared`], so it
+    /// can be replaced by [`CPackEditor::save`]. Opening a read-only file thus fail: use
+    /// [`CPackEditor::open_read_only`] for them.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CPackError> {
+        let path = path.as_ref();
+        let file = crate::shared_file::shared_options().write(true).open(path)?;
+        let mut editor = Self::new(CPack::new_from_file(file)?);
+        editor.path = Some(path.to_path_buf());
+        Ok(editor)
+    }
+
+    /// Same as [`CPackEditor::open`], but only open the archive for reading, so read-only files can be edited too.
+    ///
+    /// The modifications can then only be written to another file, or with [`CPackEditor::save`]: the in-place
+    /// commits return [`CPackError::InPlaceEditImpossible`].
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, CPackError> {
+        let path = path.as_ref();
+        let file = crate::shared_file::shared_options().open(path)?;
+        let mut editor = Self::new(CPack::new_from_file(file)?);
+        editor.path = Some(path.to_path_buf());
+        editor.read_only = true;
+        Ok(editor)
+    }
+
+    /// Write the modified archive over the file it was opened from, then continue editing the saved archive.
+    ///
+    /// The archive is written with the layout of the original one, as inferred by [`CPack::infer_packer_profile`],
+    /// through a temporary file, like [`CPackEditor::save_atomic`]. An [`io::ErrorKind::InvalidInput`] error is returned
+    /// if the editor wasn't created by [`CPackEditor::open`] or [`CPackEditor::open_read_only`].
+    pub fn save(&mut self) -> Result<(), CPackError> {
+        let path = self.path.clone().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the archive wasn't opened from a path")
+        })?;
+        self.save_as(path)
+    }
+
+    /// Same as [`CPackEditor::save`], but write the modified archive to ``path``, that become the archive being edited
+    pub fn save_as<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CPackError> {
+        let profile = self.pack.infer_packer_profile()?;
+        self.save_atomic(&path, profile)?;
+        *self = if self.read_only {
+            Self::open_read_only(path)?
+        } else {
+            Self::open(path)?
+        };
+        Ok(())
+    }
+}
+
+#[cfg(feature = "file-lock")]
+impl CPackEditor<File> {
+    /// Same as [`CPackEditor::commit_in_place`], but hold an exclusive advisory lock on the archive while writing
+    /// (``flock`` on Unix, ``LockFileEx`` on Windows).
+    ///
+    /// If another program hold a lock on it, like another editor using this function, [`CPackError::ArchiveLocked`]
+    /// is returned without writing anything. Programs that don't lock the file aren't prevented from modifying it.
+    ///
+    /// The header is read again once the lock is held. If it changed since the archive was opened or last committed,
+    /// like when another editor committed in the meantime, [`CPackError::ArchiveModified`] is returned without writing
+    /// anything: open the archive again to edit its new content.
+    ///
+    /// Available with the ``file-lock`` feature, as file locks need Rust 1.89.
+    pub fn commit_in_place_locked(&mut self) -> Result<(), CPackError> {
+        if !self.can_commit_in_place() {
+            return Err(CPackError::InPlaceEditImpossible);
+        };
+        match self.pack.lock_file()?.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => return Err(CPackError::ArchiveLocked),
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        };
+        let result = self.check_unmodified().and_then(|()| self.write_in_place(None));
+        let unlocked = self.pack.lock_file()?.unlock();
+        result?;
+        Ok(unlocked?)
+    }
+
+    /// Check that the header of the archive is still the one it was parsed from
+    fn check_unmodified(&self) -> Result<(), CPackError> {
+        let mut file = self.pack.lock_file()?;
+        let options = ParseOptions {
+            variant: self.pack.variant(),
+            strictness: Strictness::Lax,
+            skip_validation: true,
+            ..Default::default()
+        };
+        let index = match CPackIndex::parse_with_options(&mut *file, options) {
+            Ok(index) => index,
+            Err(CPackError::IOError(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(CPackError::ArchiveModified)
+            }
+            Err(err) => return Err(err),
+        };
+        if index.entry_table() != self.pack.entry_table() || index.archive_len() != self.pack.archive_len() {
+            return Err(CPackError::ArchiveModified);
+        };
+        Ok(())
+    }
+}
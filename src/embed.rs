@@ -0,0 +1,16 @@
+/// Embed the archive at the given path in the binary, and parse it. Return a ``Result<CPack<Cursor<&'static [u8]>>, CPackError>``.
+///
+/// The path is relative to the current file, like with [`include_bytes`]. Reading files from the archive is done from
+/// memory, without any IO at runtime, which is handy for small assets bundled with a tool, and for tests.
+///
+/// # Examples
+/// ```ignore
+/// let pack = pmd_cpack::include_cpack!("../assets/fonts.bin").unwrap();
+/// let banner = pack.read_file_to_vec(0).unwrap();
+/// ```
+#[macro_export]
+macro_rules! include_cpack {
+    ($path:expr) => {
+        $crate::CPack::new_from_file(::std::io::Cursor::new(&include_bytes!($path)[..]))
+    };
+}
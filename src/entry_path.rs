@@ -0,0 +1,69 @@
+use crate::{CPack, CPackError, EntryId};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const URL_SCHEME: &str = "cpack://";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A reference to a file inside an archive on disk, to be stored in configuration files.
+///
+/// It can be written as ``<archive path>#<id>`` (like ``MONSTER/m_attack.bin#42``), or as ``cpack://<archive path>/<id>``.
+/// It is displayed in the first form.
+pub struct EntryPath {
+    /// The path of the archive
+    pub archive: PathBuf,
+    /// The id of the file in the archive
+    pub id: EntryId,
+}
+
+impl EntryPath {
+    /// Create a reference to the file with the given id in the archive at ``archive``
+    pub fn new<P: Into<PathBuf>>(archive: P, id: EntryId) -> Self {
+        Self {
+            archive: archive.into(),
+            id,
+        }
+    }
+
+    /// Return this reference with a relative archive path made relative to ``base`` instead, typically the directory
+    /// of the configuration file it was read from. Absolute paths are kept as-is.
+    pub fn relative_to<P: AsRef<Path>>(&self, base: P) -> Self {
+        Self {
+            archive: base.as_ref().join(&self.archive),
+            id: self.id,
+        }
+    }
+
+    /// Open the archive and read the whole referenced file
+    pub fn read(&self) -> Result<Vec<u8>, CPackError> {
+        let pack = CPack::new_from_file(BufReader::new(File::open(&self.archive)?))?;
+        pack.read_file_by_id(self.id)
+    }
+}
+
+impl FromStr for EntryPath {
+    type Err = CPackError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let split = match text.strip_prefix(URL_SCHEME) {
+            Some(rest) => rest.rsplit_once('/'),
+            None => text.rsplit_once('#'),
+        };
+        match split {
+            Some((archive, id)) if !archive.is_empty() => match id.parse::<EntryId>() {
+                Ok(id) => Ok(Self::new(archive, id)),
+                Err(_) => Err(CPackError::InvalidEntryPath(text.to_string())),
+            },
+            _ => Err(CPackError::InvalidEntryPath(text.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for EntryPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.archive.display(), self.id)
+    }
+}
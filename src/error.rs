@@ -0,0 +1,384 @@
+use std::fmt;
+use std::error::Error;
+use std::io;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A copy of the raw bytes around the location of a parse error, to help diagnosing corrupted archives
+pub struct ByteSnippet {
+    /// The offset of the first byte of the snippet in the archive
+    pub offset: u64,
+    /// The bytes of the archive, starting at ``offset``
+    pub bytes: Vec<u8>,
+}
+
+impl fmt::Display for ByteSnippet {
+    /// Display the snippet as an hexadecimal dump, with 16 bytes per line prefixed by their offset
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (line_nb, line) in self.bytes.chunks(16).enumerate() {
+            if line_nb != 0 {
+                writeln!(f)?;
+            };
+            write!(f, "{:08x}:", self.offset + line_nb as u64 * 16)?;
+            for byte in line {
+                write!(f, " {:02x}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+/// Possible error that may happen with CPack
+#[non_exhaustive]
+pub enum CPackError {
+    IOError(io::Error),
+    PoisonedLock,
+    FourFirstByteNotZero([u8; 4], ByteSnippet),
+    EndOfFileOutOfScope(u32, u64, u64, ByteSnippet),
+    EndOfHeaderNotZero(u64, [u8; 8], ByteSnippet),
+    PartitionCreationError(io::Error),
+    OffsetOverflow(u64),
+    EntryNotFound(usize),
+    InPlaceEditImpossible,
+    InvalidSidecar(usize),
+    RangeOutOfEntry(usize, u64, u64),
+    EntryOverlapHeader(u32),
+    InconsistentPadding(u64, u8, u8),
+    IndexMismatch(u64, u64),
+    ReadLimitExceeded(u64, u64),
+    Cancelled,
+    ChecksumMismatch(usize),
+    InvalidEntryPath(String),
+    InvalidTarEntry(String),
+    MissingTarEntry(usize),
+    ArchiveLocked,
+    SizeOverflow,
+    UnfilledEntry(usize),
+    ReservedLengthMismatch(usize, u64, u64),
+    InvalidExtractedName(usize, String),
+    InvalidManifest(usize),
+    InvalidRegistry(usize),
+    ArchiveModified,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+/// A stable numeric identifier for each kind of [`CPackError`], for FFI, exit codes and bindings.
+///
+/// Values are never reused nor changed once released. 0 is never used, so it can mean success.
+#[non_exhaustive]
+pub enum ErrorCode {
+    IOError = 1,
+    PoisonedLock = 2,
+    FourFirstByteNotZero = 3,
+    EndOfFileOutOfScope = 4,
+    EndOfHeaderNotZero = 5,
+    PartitionCreationError = 6,
+    OffsetOverflow = 7,
+    EntryNotFound = 8,
+    InPlaceEditImpossible = 9,
+    InvalidSidecar = 10,
+    RangeOutOfEntry = 11,
+    EntryOverlapHeader = 12,
+    InconsistentPadding = 13,
+    IndexMismatch = 14,
+    ReadLimitExceeded = 15,
+    Cancelled = 16,
+    ChecksumMismatch = 17,
+    InvalidEntryPath = 18,
+    InvalidTarEntry = 19,
+    MissingTarEntry = 20,
+    ArchiveLocked = 21,
+    SizeOverflow = 22,
+    UnfilledEntry = 23,
+    ReservedLengthMismatch = 24,
+    InvalidExtractedName = 25,
+    InvalidManifest = 26,
+    InvalidRegistry = 27,
+    ArchiveModified = 28,
+}
+
+impl ErrorCode {
+    const ALL: [ErrorCode; 28] = [
+        Self::IOError,
+        Self::PoisonedLock,
+        Self::FourFirstByteNotZero,
+        Self::EndOfFileOutOfScope,
+        Self::EndOfHeaderNotZero,
+        Self::PartitionCreationError,
+        Self::OffsetOverflow,
+        Self::EntryNotFound,
+        Self::InPlaceEditImpossible,
+        Self::InvalidSidecar,
+        Self::RangeOutOfEntry,
+        Self::EntryOverlapHeader,
+        Self::InconsistentPadding,
+        Self::IndexMismatch,
+        Self::ReadLimitExceeded,
+        Self::Cancelled,
+        Self::ChecksumMismatch,
+        Self::InvalidEntryPath,
+        Self::InvalidTarEntry,
+        Self::MissingTarEntry,
+        Self::ArchiveLocked,
+        Self::SizeOverflow,
+        Self::UnfilledEntry,
+        Self::ReservedLengthMismatch,
+        Self::InvalidExtractedName,
+        Self::InvalidManifest,
+        Self::InvalidRegistry,
+        Self::ArchiveModified,
+    ];
+
+    /// Return the numeric value of this code
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+
+    /// Return the [`ErrorCode`] with the given numeric value, or None if there is none
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|error_code| error_code.code() == code)
+    }
+
+    /// Return a stable textual identifier for this code, like ``entry-not-found``, to be used as the key of a translated
+    /// message (like a Fluent message id). The parameters of the message are given by [`CPackError::arguments`].
+    pub fn identifier(self) -> &'static str {
+        match self {
+            Self::IOError => "io-error",
+            Self::PoisonedLock => "poisoned-lock",
+            Self::FourFirstByteNotZero => "four-first-byte-not-zero",
+            Self::EndOfFileOutOfScope => "end-of-file-out-of-scope",
+            Self::EndOfHeaderNotZero => "end-of-header-not-zero",
+            Self::PartitionCreationError => "partition-creation-error",
+            Self::OffsetOverflow => "offset-overflow",
+            Self::EntryNotFound => "entry-not-found",
+            Self::InPlaceEditImpossible => "in-place-edit-impossible",
+            Self::InvalidSidecar => "invalid-sidecar",
+            Self::RangeOutOfEntry => "range-out-of-entry",
+            Self::EntryOverlapHeader => "entry-overlap-header",
+            Self::InconsistentPadding => "inconsistent-padding",
+            Self::IndexMismatch => "index-mismatch",
+            Self::ReadLimitExceeded => "read-limit-exceeded",
+            Self::Cancelled => "cancelled",
+            Self::ChecksumMismatch => "checksum-mismatch",
+            Self::InvalidEntryPath => "invalid-entry-path",
+            Self::InvalidTarEntry => "invalid-tar-entry",
+            Self::MissingTarEntry => "missing-tar-entry",
+            Self::ArchiveLocked => "archive-locked",
+            Self::SizeOverflow => "size-overflow",
+            Self::UnfilledEntry => "unfilled-entry",
+            Self::ReservedLengthMismatch => "reserved-length-mismatch",
+            Self::InvalidExtractedName => "invalid-extracted-name",
+            Self::InvalidManifest => "invalid-manifest",
+            Self::InvalidRegistry => "invalid-registry",
+            Self::ArchiveModified => "archive-modified",
+        }
+    }
+}
+
+impl From<ErrorCode> for u32 {
+    fn from(code: ErrorCode) -> u32 {
+        code.code()
+    }
+}
+
+impl CPackError {
+    /// Return the stable code identifying the kind of this error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::IOError(_) => ErrorCode::IOError,
+            Self::PoisonedLock => ErrorCode::PoisonedLock,
+            Self::FourFirstByteNotZero(_, _) => ErrorCode::FourFirstByteNotZero,
+            Self::EndOfFileOutOfScope(_, _, _, _) => ErrorCode::EndOfFileOutOfScope,
+            Self::EndOfHeaderNotZero(_, _, _) => ErrorCode::EndOfHeaderNotZero,
+            Self::PartitionCreationError(_) => ErrorCode::PartitionCreationError,
+            Self::OffsetOverflow(_) => ErrorCode::OffsetOverflow,
+            Self::EntryNotFound(_) => ErrorCode::EntryNotFound,
+            Self::InPlaceEditImpossible => ErrorCode::InPlaceEditImpossible,
+            Self::InvalidSidecar(_) => ErrorCode::InvalidSidecar,
+            Self::RangeOutOfEntry(_, _, _) => ErrorCode::RangeOutOfEntry,
+            Self::EntryOverlapHeader(_) => ErrorCode::EntryOverlapHeader,
+            Self::InconsistentPadding(_, _, _) => ErrorCode::InconsistentPadding,
+            Self::IndexMismatch(_, _) => ErrorCode::IndexMismatch,
+            Self::ReadLimitExceeded(_, _) => ErrorCode::ReadLimitExceeded,
+            Self::Cancelled => ErrorCode::Cancelled,
+            Self::ChecksumMismatch(_) => ErrorCode::ChecksumMismatch,
+            Self::InvalidEntryPath(_) => ErrorCode::InvalidEntryPath,
+            Self::InvalidTarEntry(_) => ErrorCode::InvalidTarEntry,
+            Self::MissingTarEntry(_) => ErrorCode::MissingTarEntry,
+            Self::ArchiveLocked => ErrorCode::ArchiveLocked,
+            Self::SizeOverflow => ErrorCode::SizeOverflow,
+            Self::UnfilledEntry(_) => ErrorCode::UnfilledEntry,
+            Self::ReservedLengthMismatch(_, _, _) => ErrorCode::ReservedLengthMismatch,
+            Self::InvalidExtractedName(_, _) => ErrorCode::InvalidExtractedName,
+            Self::InvalidManifest(_) => ErrorCode::InvalidManifest,
+            Self::InvalidRegistry(_) => ErrorCode::InvalidRegistry,
+            Self::ArchiveModified => ErrorCode::ArchiveModified,
+        }
+    }
+
+    /// Return the values interpolated in the message of this error, by name, so it can be displayed in another language.
+    ///
+    /// The message itself is identified by [`ErrorCode::identifier`]. The names are stable, and are the same for every
+    /// language. Numbers are formatted in decimal, and bytes as a list like ``[1, 2, 3, 4]``.
+    pub fn arguments(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::IOError(err) | Self::PartitionCreationError(err) => vec![("error", err.to_string())],
+            Self::FourFirstByteNotZero(value, _) => vec![("bytes", format!("{:?}", value))],
+            Self::EndOfFileOutOfScope(file_id, end_of_out_file, end_of_source_file, _) => vec![
+                ("id", file_id.to_string()),
+                ("file-end", end_of_out_file.to_string()),
+                ("archive-end", end_of_source_file.to_string()),
+            ],
+            Self::EndOfHeaderNotZero(start_end_of_header, value, _) => vec![
+                ("offset", start_end_of_header.to_string()),
+                ("bytes", format!("{:?}", value)),
+            ],
+            Self::OffsetOverflow(offset) => vec![("offset", offset.to_string())],
+            Self::EntryNotFound(id)
+            | Self::ChecksumMismatch(id)
+            | Self::MissingTarEntry(id)
+            | Self::UnfilledEntry(id) => vec![("id", id.to_string())],
+            Self::InvalidSidecar(line) | Self::InvalidManifest(line) | Self::InvalidRegistry(line) => {
+                vec![("line", line.to_string())]
+            }
+            Self::RangeOutOfEntry(id, start, len) => vec![
+                ("id", id.to_string()),
+                ("start", start.to_string()),
+                ("length", len.to_string()),
+            ],
+            Self::EntryOverlapHeader(file_id) => vec![("id", file_id.to_string())],
+            Self::InconsistentPadding(offset, expected, found) => vec![
+                ("offset", offset.to_string()),
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            Self::IndexMismatch(expected, found) => vec![
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            Self::ReadLimitExceeded(len, limit) => vec![
+                ("length", len.to_string()),
+                ("limit", limit.to_string()),
+            ],
+            Self::InvalidEntryPath(text) => vec![("text", text.clone())],
+            Self::InvalidTarEntry(name) => vec![("name", name.clone())],
+            Self::ReservedLengthMismatch(id, expected, found) => vec![
+                ("id", id.to_string()),
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            Self::InvalidExtractedName(id, name) => vec![("id", id.to_string()), ("name", name.clone())],
+            Self::PoisonedLock
+            | Self::InPlaceEditImpossible
+            | Self::Cancelled
+            | Self::ArchiveLocked
+            | Self::ArchiveModified
+            | Self::SizeOverflow => Vec::new(),
+        }
+    }
+
+    /// Return the raw bytes around the location of the error, for errors that happen while parsing the header
+    pub fn snippet(&self) -> Option<&ByteSnippet> {
+        match self {
+            Self::FourFirstByteNotZero(_, snippet)
+            | Self::EndOfFileOutOfScope(_, _, _, snippet)
+            | Self::EndOfHeaderNotZero(_, _, snippet) => Some(snippet),
+            _ => None,
+        }
+    }
+}
+
+impl Error for CPackError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::IOError(err) | Self::PartitionCreationError(err) => {
+                Some(err)
+            },
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for CPackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CPackError::IOError(_) => write!(f, "an error happened while performing an IO on the input file"),
+            CPackError::PoisonedLock => write!(f, "the lock that hold the file is unecpetitly poisoned"),
+            CPackError::FourFirstByteNotZero(value, _) => write!(f, "the four first bytes of the file should be zero, but they are {:?}.", value),
+            CPackError::EndOfFileOutOfScope(file_id, end_of_out_file, end_of_source_file, _) => write!(f, "The file (id: {}) end after the source file end (source file end: {}, output file end in the source file: {})", file_id, end_of_source_file, end_of_out_file),
+            CPackError::EndOfHeaderNotZero(start_end_of_header, value, _) => write!(f, "the end of the header should be 8 zero bytes, but found {:?} (end of the header start at {})", value, start_end_of_header),
+            CPackError::PartitionCreationError(_) => write!(f, "unable to create a sub file partition"),
+            CPackError::OffsetOverflow(offset) => write!(f, "the offset {} can't be stored in the 32 bits field of a cpack file", offset),
+            CPackError::EntryNotFound(id) => write!(f, "there is no file with the id {} in the archive", id),
+            CPackError::InPlaceEditImpossible => write!(f, "the modifications can't be applied in place, as they change the layout of the archive"),
+            CPackError::InvalidSidecar(line) => write!(f, "the sidecar file is invalid at line {}", line),
+            CPackError::RangeOutOfEntry(id, start, len) => write!(f, "the range of {} bytes starting at {} doesn't fit in the file with the id {}", len, start, id),
+            CPackError::EntryOverlapHeader(file_id) => write!(f, "the file (id: {}) start inside the header", file_id),
+            CPackError::InconsistentPadding(offset, expected, found) => write!(f, "the padding byte at {} is {}, but previous padding bytes are {}", offset, found, expected),
+            CPackError::IndexMismatch(expected, found) => write!(f, "the index is for an archive of {} bytes, but the file is {} bytes long", expected, found),
+            CPackError::ReadLimitExceeded(len, limit) => write!(f, "reading {} bytes at once would exceed the read limit of {} bytes", len, limit),
+            CPackError::Cancelled => write!(f, "the operation was cancelled"),
+            CPackError::ChecksumMismatch(id) => write!(f, "the content of the file with the id {} doesn't match its checksum", id),
+            CPackError::InvalidEntryPath(text) => write!(f, "{:?} isn't a valid reference to a file in an archive", text),
+            CPackError::InvalidTarEntry(name) => write!(f, "the file {:?} of the tar stream isn't named after the id of a file, or is present twice", name),
+            CPackError::MissingTarEntry(id) => write!(f, "there is no file with the id {} in the tar stream, but there are files with greater ids", id),
+            CPackError::ArchiveLocked => write!(f, "the archive is locked by another program editing it"),
+            CPackError::SizeOverflow => write!(f, "the total size of the files doesn't fit in 64 bits"),
+            CPackError::UnfilledEntry(id) => write!(f, "the space reserved for the file with the id {} was never filled", id),
+            CPackError::ReservedLengthMismatch(id, expected, found) => write!(f, "{} bytes were reserved for the file with the id {}, but it was filled with {} bytes", expected, id, found),
+            CPackError::InvalidExtractedName(id, name) => write!(f, "the file with the id {} can't be extracted with the name {:?}, that isn't a plain file name", id, name),
+            CPackError::InvalidManifest(line) => write!(f, "the manifest of the extracted archive is invalid at line {}", line),
+            CPackError::InvalidRegistry(line) => write!(f, "the registry of known archives is invalid at line {}", line),
+            CPackError::ArchiveModified => write!(f, "the archive was modified by another program since it was opened"),
+        }
+    }
+}
+
+impl From<io::Error> for CPackError {
+    fn from(err: io::Error) -> CPackError {
+        Self::IOError(err)
+    }
+}
+
+impl From<CPackError> for io::Error {
+    /// Convert to an [`io::Error`] with a matching [`io::ErrorKind`], keeping the [`CPackError`] as the inner error.
+    ///
+    /// An [`CPackError::IOError`] is unwrapped, so the original error is returned as-is.
+    fn from(err: CPackError) -> io::Error {
+        if let CPackError::IOError(io_err) = err {
+            return io_err;
+        };
+        let kind = match &err {
+            CPackError::IOError(io_err) | CPackError::PartitionCreationError(io_err) => io_err.kind(),
+            // not Interrupted, as the standard library retry the operations interrupted that way
+            CPackError::PoisonedLock | CPackError::Cancelled => io::ErrorKind::Other,
+            CPackError::FourFirstByteNotZero(_, _)
+            | CPackError::EndOfFileOutOfScope(_, _, _, _)
+            | CPackError::EndOfHeaderNotZero(_, _, _)
+            | CPackError::InvalidSidecar(_)
+            | CPackError::InvalidManifest(_)
+            | CPackError::InvalidRegistry(_)
+            | CPackError::EntryOverlapHeader(_)
+            | CPackError::InconsistentPadding(_, _, _)
+            | CPackError::IndexMismatch(_, _)
+            | CPackError::ChecksumMismatch(_)
+            | CPackError::InvalidTarEntry(_)
+            | CPackError::MissingTarEntry(_)
+            | CPackError::SizeOverflow => io::ErrorKind::InvalidData,
+            CPackError::OffsetOverflow(_)
+            | CPackError::RangeOutOfEntry(_, _, _)
+            | CPackError::InvalidEntryPath(_)
+            | CPackError::UnfilledEntry(_)
+            | CPackError::ReservedLengthMismatch(_, _, _)
+            | CPackError::InvalidExtractedName(_, _) => {
+                io::ErrorKind::InvalidInput
+            }
+            CPackError::EntryNotFound(_) => io::ErrorKind::NotFound,
+            CPackError::InPlaceEditImpossible => io::ErrorKind::Unsupported,
+            CPackError::ReadLimitExceeded(_, _) => io::ErrorKind::OutOfMemory,
+            CPackError::ArchiveLocked => io::ErrorKind::WouldBlock,
+            CPackError::ArchiveModified => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err)
+    }
+}
@@ -0,0 +1,252 @@
+use crate::hash::{sha256, to_hex};
+use crate::{CPack, CPackError, EntryProcessor, LockStrategy, ProcessorChain};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+#[derive(Debug, Clone, Default)]
+/// A flag used to ask an [`ExtractJob`] to stop. Every clone share the same flag.
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Create a token that isn't cancelled yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the jobs using this token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Return true if [`CancelToken::cancel`] was called on this token or one of its clones
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The name of the checksum file written by [`CPack::extract_all_with_options`]
+const CHECKSUM_FILE_NAME: &str = "SHA256SUMS";
+
+/// Return the name of the file the file with the given id is extracted to
+pub(crate) fn extracted_name(id: usize) -> String {
+    format!("{}.bin", id)
+}
+
+/// Return true if ``name`` can be used as the name of an extracted file: not empty, and not a path to another directory
+pub(crate) fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+/// Return the path the file with the given id is extracted to in ``directory``
+pub(crate) fn extracted_path(directory: &Path, id: usize) -> PathBuf {
+    directory.join(extracted_name(id))
+}
+
+#[derive(Debug, Clone, Default)]
+/// Options used when extracting an archive to a directory
+pub struct ExtractOptions {
+    /// If true, a ``SHA256SUMS`` file is also written in the directory, in the format used by ``sha256sum``,
+    /// so the extracted files can be verified later with ``sha256sum -c SHA256SUMS``
+    pub write_checksums: bool,
+    /// If true, a ``MANIFEST`` file is also written in the directory, describing the layout of the archive (the position
+    /// of the files, the padding, the trailing data...), so [`crate::pack_from_manifest`] can rebuild it byte for byte
+    pub write_manifest: bool,
+    /// The processors applied to every file before it is written. The ``SHA256SUMS`` file contain the hash of the
+    /// processed files, but the ``MANIFEST`` still describe the archive, so [`crate::pack_from_manifest`] can only
+    /// rebuild it if the processors didn't change the files.
+    pub processors: ProcessorChain,
+}
+
+impl ExtractOptions {
+    /// Return options that also write a ``SHA256SUMS`` file
+    pub fn with_checksums() -> Self {
+        Self {
+            write_checksums: true,
+            ..Self::default()
+        }
+    }
+
+    /// Return options that also write a ``MANIFEST`` file
+    pub fn with_manifest() -> Self {
+        Self {
+            write_manifest: true,
+            ..Self::default()
+        }
+    }
+
+    /// Return options that apply the given processors to every file
+    pub fn with_processors(processors: ProcessorChain) -> Self {
+        Self {
+            processors,
+            ..Self::default()
+        }
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Extract every file of the archive into ``directory``, creating it if needed. Each file is written to ``<id>.bin``.
+    pub fn extract_all<P: AsRef<Path>>(&self, directory: P) -> Result<(), CPackError> {
+        self.extract_all_with_options(directory, &ExtractOptions::default())
+    }
+
+    /// Same as [`CPack::extract_all`], using the given [`ExtractOptions`].
+    ///
+    /// The processors of the options are called with each file in order of id, and may change its name and content.
+    pub fn extract_all_with_options<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        options: &ExtractOptions,
+    ) -> Result<(), CPackError> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let mut hashes = Vec::new();
+        let mut content = Vec::new();
+        for id in 0..self.len() {
+            self.read_file_with_buffer(id, &mut content)?;
+            let mut name = extracted_name(id);
+            options.processors.process(id, &mut name, &mut content)?;
+            if !is_plain_file_name(&name) {
+                return Err(CPackError::InvalidExtractedName(id, name));
+            };
+            fs::write(directory.join(&name), &content)?;
+            if options.write_checksums {
+                hashes.push((sha256(&content), name));
+            };
+        }
+        if options.write_checksums {
+            let mut out = BufWriter::new(File::create(directory.join(CHECKSUM_FILE_NAME))?);
+            for (hash, name) in &hashes {
+                writeln!(out, "{}  {}", to_hex(hash), name)?;
+            }
+            out.flush()?;
+        };
+        if options.write_manifest {
+            self.write_manifest(directory)?;
+        };
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+/// The state shared between an [`ExtractJob`] and its workers
+struct JobState {
+    /// The id of the next file to extract
+    next_id: AtomicUsize,
+    /// The number of file completely extracted
+    extracted: AtomicUsize,
+    /// Set when a worker failed, so the others stop too
+    failed: AtomicBool,
+}
+
+/// The extraction of every file of an archive to a directory, running on background threads.
+///
+/// Each file is written to ``<id>.bin``. Cancellation is cooperative: the files being extracted are finished,
+/// then the workers stop.
+pub struct ExtractJob {
+    total: usize,
+    state: Arc<JobState>,
+    cancel: CancelToken,
+    workers: Vec<JoinHandle<Result<(), CPackError>>>,
+}
+
+impl ExtractJob {
+    /// Start extracting every file of ``pack`` into ``directory``, creating it if needed, on one worker thread per available CPU.
+    pub fn start<F, P>(pack: Arc<CPack<F>>, directory: P) -> Result<Self, CPackError>
+    where
+        F: Read + Seek + Send + 'static,
+        P: Into<PathBuf>,
+    {
+        Self::start_with_token(pack, directory, CancelToken::new())
+    }
+
+    /// Same as [`ExtractJob::start`], but the job can also be cancelled with the given token, that may be shared with other jobs
+    pub fn start_with_token<F, P>(pack: Arc<CPack<F>>, directory: P, cancel: CancelToken) -> Result<Self, CPackError>
+    where
+        F: Read + Seek + Send + 'static,
+        P: Into<PathBuf>,
+    {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        let total = pack.len();
+        let state = Arc::new(JobState::default());
+        let nb_workers = thread::available_parallelism()
+            .map(|nb| nb.get())
+            .unwrap_or(1)
+            .min(total)
+            .max(1);
+        let workers = (0..nb_workers)
+            .map(|_| {
+                let pack = pack.clone();
+                let directory = directory.clone();
+                let state = state.clone();
+                let cancel = cancel.clone();
+                thread::spawn(move || -> Result<(), CPackError> {
+                    while !cancel.is_cancelled() && !state.failed.load(Ordering::Relaxed) {
+                        let id = state.next_id.fetch_add(1, Ordering::Relaxed);
+                        if id >= total {
+                            break;
+                        };
+                        let result = pack
+                            .read_file_to_vec(id)
+                            .and_then(|content| Ok(fs::write(extracted_path(&directory, id), content)?));
+                        if let Err(err) = result {
+                            state.failed.store(true, Ordering::Relaxed);
+                            return Err(err);
+                        };
+                        state.extracted.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        Ok(Self {
+            total,
+            state,
+            cancel,
+            workers,
+        })
+    }
+
+    /// Return the number of file already extracted, and the total number of file to extract
+    pub fn progress(&self) -> (usize, usize) {
+        (self.state.extracted.load(Ordering::Relaxed), self.total)
+    }
+
+    /// Return the token that cancel this job
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Ask the job to stop. Use [`ExtractJob::join`] to wait for it to actually stop.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Return true if every worker stopped, because the job completed, failed or was cancelled
+    pub fn is_finished(&self) -> bool {
+        self.workers.iter().all(JoinHandle::is_finished)
+    }
+
+    /// Wait for the job to stop. Return the first error encountered, or [`CPackError::Cancelled`] if the job was
+    /// cancelled before every file was extracted.
+    pub fn join(self) -> Result<(), CPackError> {
+        let mut result = Ok(());
+        for worker in self.workers {
+            let worker_result = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            if result.is_ok() {
+                result = worker_result;
+            };
+        }
+        result?;
+        if self.state.extracted.load(Ordering::Relaxed) < self.total {
+            return Err(CPackError::Cancelled);
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use sha2::{Digest, Sha256};
+use std::io::{self, Read, Write};
+
+/// The size of the buffer used to hash data read from a reader. Big enough for the per-call overhead of the
+/// reads and of the hasher to be negligible, sha2 using the SHA extensions of the CPU when available.
+pub(crate) const HASH_CHUNK_LEN: usize = 0x100000;
+
+/// Return the lowercase hexadecimal representation of ``bytes``
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parse a SHA-256 written in hexadecimal, as returned by [`to_hex`]
+pub(crate) fn sha256_from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    };
+    let mut result = [0; 32];
+    for (byte, chunk) in result.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+    }
+    Some(result)
+}
+
+/// Return the SHA-256 of ``bytes``
+pub(crate) fn sha256(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Return the SHA-256 of the ``len`` next bytes of ``reader``, reading them in chunks of the size of ``buffer``.
+///
+/// The same buffer can be reused when hashing many files. It must not be empty if ``len`` isn't 0.
+pub(crate) fn sha256_reader<R: Read>(reader: &mut R, mut len: u64, buffer: &mut [u8]) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let buffer_len = buffer.len() as u64;
+    while len > 0 {
+        let chunk = &mut buffer[..len.min(buffer_len) as usize];
+        reader.read_exact(chunk)?;
+        hasher.update(&*chunk);
+        len -= chunk.len() as u64;
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// A writer that compute the SHA-256 of everything written through it
+pub(crate) struct HashingWriter<'w, W: Write> {
+    inner: &'w mut W,
+    hasher: Sha256,
+}
+
+impl<'w, W: Write> HashingWriter<'w, W> {
+    pub(crate) fn new(inner: &'w mut W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub(crate) fn finalize(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+impl<'w, W: Write> Write for HashingWriter<'w, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
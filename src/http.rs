@@ -0,0 +1,87 @@
+use crate::range::{RangeFetch, RangeReader};
+use std::io::{self, Read, Seek, SeekFrom};
+
+#[derive(Debug)]
+struct HttpFetcher {
+    agent: ureq::Agent,
+    url: String,
+}
+
+impl RangeFetch for HttpFetcher {
+    fn fetch(&mut self, start: u64, len: u64) -> io::Result<Vec<u8>> {
+        if len == 0 {
+            return Ok(Vec::new());
+        };
+        let response = self
+            .agent
+            .get(&self.url)
+            .set("Range", &format!("bytes={}-{}", start, start + len - 1))
+            .call()
+            .map_err(io::Error::other)?;
+        if response.status() != 206 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the server doesn't support range requests",
+            ));
+        };
+        let mut bytes = Vec::with_capacity(len as usize);
+        response.into_reader().take(len).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+#[derive(Debug)]
+/// A remote file read with HTTP range requests, available with the ``http`` feature.
+///
+/// Give it to [`crate::CPack::new_from_file`] to parse the header and read single files of a remote archive
+/// without downloading all of it. Reads are grouped by block of at least 64 KiB, and the last block is cached.
+pub struct HttpStorage {
+    reader: RangeReader<HttpFetcher>,
+}
+
+impl HttpStorage {
+    /// Open the file at ``url``, sending a HEAD request to get its length
+    pub fn open<S: Into<String>>(url: S) -> io::Result<Self> {
+        Self::with_agent(ureq::Agent::new(), url)
+    }
+
+    /// Same as [`HttpStorage::open`], sending the requests with ``agent``, to configure timeouts or proxies
+    pub fn with_agent<S: Into<String>>(agent: ureq::Agent, url: S) -> io::Result<Self> {
+        let url = url.into();
+        let response = agent.head(&url).call().map_err(io::Error::other)?;
+        let len = response
+            .header("Content-Length")
+            .and_then(|len| len.parse().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the server didn't return the length of the file",
+                )
+            })?;
+        Ok(Self {
+            reader: RangeReader::new(HttpFetcher { agent, url }, len),
+        })
+    }
+
+    /// Return the length of the remote file, as reported by the server
+    pub fn len(&self) -> u64 {
+        self.reader.len()
+    }
+
+    /// Return true if the remote file is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Read for HttpStorage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl Seek for HttpStorage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{CPack, CPackError, EntryInfo, LockStrategy};
+use io_partition::PartitionMutex;
+use std::fmt;
+use std::io::{Read, Seek};
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+/// The id of a file in an archive: its position in the table of content.
+///
+/// Most functions of [`CPack`] take the id as an ``usize``, use [`EntryId::index`] or [`From`] to convert it.
+/// [`CPack::get_file_by_id`], [`CPack::read_file_by_id`] and [`CPack::entry_info_by_id`] take either.
+/// This type exist so ids can't be mixed up with offsets or lengths in code using this crate.
+pub struct EntryId(pub usize);
+
+impl EntryId {
+    /// Return the id as an index in the table of content
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl From<usize> for EntryId {
+    fn from(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+impl From<EntryId> for usize {
+    fn from(id: EntryId) -> usize {
+        id.0
+    }
+}
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for EntryId {
+    type Err = ParseIntError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(Self(text.parse()?))
+    }
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Same as [`CPack::get_file`], taking an [`EntryId`] or an ``usize``
+    pub fn get_file_by_id(&self, id: impl Into<EntryId>) -> Result<PartitionMutex<F>, CPackError> {
+        self.get_file(id.into().index())
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return the id of every file of the archive, in order
+    pub fn ids(&self) -> impl Iterator<Item = EntryId> {
+        (0..self.len()).map(EntryId)
+    }
+
+    /// Same as [`CPack::read_file_to_vec`], taking an [`EntryId`] or an ``usize``
+    pub fn read_file_by_id(&self, id: impl Into<EntryId>) -> Result<Vec<u8>, CPackError> {
+        self.read_file_to_vec(id.into().index())
+    }
+
+    /// Same as [`CPack::entry_info`], taking an [`EntryId`] or an ``usize``
+    pub fn entry_info_by_id(&self, id: impl Into<EntryId>) -> Option<EntryInfo> {
+        self.entry_info(id.into().index())
+    }
+}
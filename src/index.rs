@@ -0,0 +1,148 @@
+use crate::{CPack, CPackError, EntryInfo, HeaderVariant, LockStrategy, ParseOptions, ParseWarning, Strictness};
+use crate::observer::ObserverSlot;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The parsed header of a cpack archive, detached from the reader it was parsed from.
+///
+/// It can be attached to another reader over the same bytes with [`CPack::with_index`], for example after reopening
+/// the file, without parsing the header again.
+///
+/// Two indexes are equal when the archives have the same layout and were parsed with the same settings. Use
+/// [`crate::content_eq`] to compare the content of the files instead.
+pub struct CPackIndex {
+    pub(crate) offset_table: Box<[EntryInfo]>,
+    pub(crate) header_len: u64,
+    pub(crate) data_start: u64,
+    pub(crate) archive_len: u64,
+    pub(crate) variant: HeaderVariant,
+    pub(crate) strictness: Strictness,
+    pub(crate) warnings: Vec<ParseWarning>,
+}
+
+impl CPackIndex {
+    /// Parse the header of the archive read by ``reader``, without keeping the reader: it is dropped once parsed.
+    ///
+    /// Only the table of content and the padding between files are read, not the files themselves. Use
+    /// [`CPackIndex::parse_with_options`] with [`ParseOptions::skip_validation`] to only read the table of content.
+    pub fn parse<R: Read + Seek>(reader: R) -> Result<Self, CPackError> {
+        Self::parse_with_options(reader, ParseOptions::default())
+    }
+
+    /// Same as [`CPackIndex::parse`], using the given [`ParseOptions`]
+    pub fn parse_with_options<R: Read + Seek>(reader: R, options: ParseOptions) -> Result<Self, CPackError> {
+        Ok(CPack::new_with_options(reader, options)?.into_index())
+    }
+
+    /// Parse the header of the archive at ``path``, closing it once done. This is meant to index many archives
+    /// quickly, like every archive of a game, then open only the ones that are needed with [`CPack::with_index`].
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CPackError> {
+        Self::open_with_options(path, ParseOptions::default())
+    }
+
+    /// Same as [`CPackIndex::open`], using the given [`ParseOptions`]
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: ParseOptions) -> Result<Self, CPackError> {
+        Self::parse_with_options(BufReader::new(File::open(path)?), options)
+    }
+
+    /// Return the number of file in the indexed archive
+    pub fn len(&self) -> usize {
+        self.offset_table.len()
+    }
+
+    /// Return true if the indexed archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the position of the file with the given id, or None if it doesn't exist
+    pub fn entry_info(&self, id: usize) -> Option<EntryInfo> {
+        self.offset_table.get(id).copied()
+    }
+
+    /// Return the position of every file of the indexed archive, indexed by id
+    pub fn entry_table(&self) -> &[EntryInfo] {
+        &self.offset_table
+    }
+
+    /// Return the length of the indexed archive, in bytes
+    pub fn archive_len(&self) -> u64 {
+        self.archive_len
+    }
+
+    /// Return the header variant the archive was parsed with
+    pub fn variant(&self) -> HeaderVariant {
+        self.variant
+    }
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Create a CPack struct from a cpack file and an index previously taken from an archive with the same content.
+    ///
+    /// The header isn't parsed again, only the length of the file is checked against the one of the indexed archive.
+    /// There is no read limit nor checksums, use [`CPack::set_read_limit`] and [`CPack::set_checksums`] to set them.
+    pub fn with_index(mut file: F, index: CPackIndex) -> Result<CPack<F>, CPackError> {
+        let file_len = file.seek(SeekFrom::End(0))?;
+        if file_len != index.archive_len {
+            return Err(CPackError::IndexMismatch(index.archive_len, file_len));
+        };
+        Ok(Self::from_index(file, index))
+    }
+
+    /// Create a CPack struct from a cpack file and an index previously parsed from it, trusting the index entirely.
+    ///
+    /// Unlike [`CPack::with_index`], nothing is read from the file, not even its length, so opening is instantaneous.
+    /// This is meant for indexes kept across process restarts along with a way to tell the archive didn't change
+    /// (like its modification time). If the file doesn't match the index, reads may fail or return wrong data, but
+    /// never read outside of the range of a file of the index.
+    pub fn from_index(file: F, index: CPackIndex) -> CPack<F> {
+        CPack {
+            offset_table: index.offset_table,
+            header_len: index.header_len,
+            data_start: index.data_start,
+            archive_len: index.archive_len,
+            variant: index.variant,
+            strictness: index.strictness,
+            warnings: index.warnings,
+            read_limit: None,
+            skip_validation: false,
+            checksums: HashMap::new(),
+            observer: ObserverSlot::default(),
+            file: Arc::new(Mutex::new(file)),
+            reader: PhantomData,
+        }
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return a copy of the parsed header of this archive
+    pub fn index(&self) -> CPackIndex {
+        CPackIndex {
+            offset_table: self.offset_table.clone(),
+            header_len: self.header_len,
+            data_start: self.data_start,
+            archive_len: self.archive_len,
+            variant: self.variant,
+            strictness: self.strictness,
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// Return the parsed header of this archive, dropping the reader
+    pub fn into_index(self) -> CPackIndex {
+        CPackIndex {
+            offset_table: self.offset_table,
+            header_len: self.header_len,
+            data_start: self.data_start,
+            archive_len: self.archive_len,
+            variant: self.variant,
+            strictness: self.strictness,
+            warnings: self.warnings,
+        }
+    }
+}
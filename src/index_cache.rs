@@ -0,0 +1,274 @@
+use crate::hash::{sha256_reader, HASH_CHUNK_LEN};
+use crate::{CPack, CPackError, CPackIndex, EntryInfo, HeaderVariant, LockStrategy, ParseOptions, ParseWarning, Strictness};
+use std::convert::TryInto;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The magic of an index cache. The last byte is the version of the format, and is incremented when it change.
+const INDEX_CACHE_MAGIC: [u8; 8] = *b"CPKIDX\0\x01";
+
+/// Return the SHA-256 of the ``header_len`` first bytes of ``file``
+fn hash_header<F: Read + Seek>(file: &mut F, header_len: u64) -> Result<[u8; 32], CPackError> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut buffer = vec![0; header_len.min(HASH_CHUNK_LEN as u64) as usize];
+    Ok(sha256_reader(file, header_len, &mut buffer)?)
+}
+
+fn variant_to_u8(variant: HeaderVariant) -> u8 {
+    match variant {
+        HeaderVariant::LittleEndian => 0,
+        HeaderVariant::BigEndian => 1,
+    }
+}
+
+fn variant_from_u8(value: u8) -> Option<HeaderVariant> {
+    match value {
+        0 => Some(HeaderVariant::LittleEndian),
+        1 => Some(HeaderVariant::BigEndian),
+        _ => None,
+    }
+}
+
+fn strictness_to_u8(strictness: Strictness) -> u8 {
+    match strictness {
+        Strictness::Lax => 0,
+        Strictness::Normal => 1,
+        Strictness::Strict => 2,
+    }
+}
+
+fn strictness_from_u8(value: u8) -> Option<Strictness> {
+    match value {
+        0 => Some(Strictness::Lax),
+        1 => Some(Strictness::Normal),
+        2 => Some(Strictness::Strict),
+        _ => None,
+    }
+}
+
+fn write_warning<W: Write>(out: &mut W, warning: &ParseWarning) -> Result<(), CPackError> {
+    match warning {
+        ParseWarning::FourFirstByteNotZero(value) => {
+            out.write_all(&[0])?;
+            out.write_all(value)?;
+        }
+        ParseWarning::EndOfFileOutOfScope(file_id) => {
+            out.write_all(&[1])?;
+            out.write_all(&file_id.to_le_bytes())?;
+        }
+        ParseWarning::EndOfHeaderNotZero(value) => {
+            out.write_all(&[2])?;
+            out.write_all(value)?;
+        }
+        ParseWarning::EntryOverlapHeader(file_id) => {
+            out.write_all(&[3])?;
+            out.write_all(&file_id.to_le_bytes())?;
+        }
+        ParseWarning::InconsistentPadding(offset, expected, found) => {
+            out.write_all(&[4])?;
+            out.write_all(&offset.to_le_bytes())?;
+            out.write_all(&[*expected, *found])?;
+        }
+        ParseWarning::TrailingData(offset, len) => {
+            out.write_all(&[5])?;
+            out.write_all(&offset.to_le_bytes())?;
+            out.write_all(&len.to_le_bytes())?;
+        }
+    };
+    Ok(())
+}
+
+/// A cursor over the content of an index cache, where every read return None once the end is reached
+struct CacheReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> CacheReader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        if self.data.len() < N {
+            return None;
+        };
+        let (value, rest) = self.data.split_at(N);
+        self.data = rest;
+        value.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take()?))
+    }
+
+    fn warning(&mut self) -> Option<ParseWarning> {
+        Some(match self.u8()? {
+            0 => ParseWarning::FourFirstByteNotZero(self.take()?),
+            1 => ParseWarning::EndOfFileOutOfScope(self.u32()?),
+            2 => ParseWarning::EndOfHeaderNotZero(self.take()?),
+            3 => ParseWarning::EntryOverlapHeader(self.u32()?),
+            4 => ParseWarning::InconsistentPadding(self.u64()?, self.u8()?, self.u8()?),
+            5 => ParseWarning::TrailingData(self.u64()?, self.u64()?),
+            _ => return None,
+        })
+    }
+}
+
+/// Parse an index cache, returning the hash of the header it was made for and the index.
+/// Return None if the cache isn't valid, including when it was written by another version of the format.
+fn read_index_cache(data: &[u8]) -> Option<([u8; 32], CPackIndex)> {
+    let mut reader = CacheReader { data };
+    if reader.take()? != INDEX_CACHE_MAGIC {
+        return None;
+    };
+    let header_hash = reader.take()?;
+    let header_len = reader.u64()?;
+    let data_start = reader.u64()?;
+    let archive_len = reader.u64()?;
+    // the header is hashed before the cache is used, so it must be in the archive
+    if header_len > archive_len || data_start > archive_len {
+        return None;
+    };
+    let variant = variant_from_u8(reader.u8()?)?;
+    let strictness = strictness_from_u8(reader.u8()?)?;
+    let count = reader.u32()? as usize;
+    // check the length before allocating, so a corrupted count can't make us allocate gigabytes
+    if count > reader.data.len() / 8 {
+        return None;
+    };
+    let mut offset_table = Vec::with_capacity(count);
+    for _ in 0..count {
+        offset_table.push(EntryInfo {
+            file_offset: reader.u32()?,
+            file_lenght: reader.u32()?,
+        });
+    }
+    let warning_count = reader.u32()? as usize;
+    let mut warnings = Vec::new();
+    for _ in 0..warning_count {
+        warnings.push(reader.warning()?);
+    }
+    if !reader.data.is_empty() {
+        return None;
+    };
+    Some((
+        header_hash,
+        CPackIndex {
+            offset_table: offset_table.into_boxed_slice(),
+            header_len,
+            data_start,
+            archive_len,
+            variant,
+            strictness,
+            warnings,
+        },
+    ))
+}
+
+impl CPackIndex {
+    /// Return the path of the index cache of the archive at ``archive_path``, as used by [`CPack::write_index_cache`]
+    /// and [`CPack::open_with_index_cache`]. It is the path of the archive with ``.index`` appended.
+    pub fn cache_path<P: AsRef<Path>>(archive_path: P) -> PathBuf {
+        let mut path = OsString::from(archive_path.as_ref().as_os_str());
+        path.push(".index");
+        PathBuf::from(path)
+    }
+
+    /// Write this index as an index cache to ``out``. ``header_hash`` is the SHA-256 of the header it was parsed from.
+    fn write_cache<W: Write>(&self, out: &mut W, header_hash: &[u8; 32]) -> Result<(), CPackError> {
+        out.write_all(&INDEX_CACHE_MAGIC)?;
+        out.write_all(header_hash)?;
+        out.write_all(&self.header_len.to_le_bytes())?;
+        out.write_all(&self.data_start.to_le_bytes())?;
+        out.write_all(&self.archive_len.to_le_bytes())?;
+        out.write_all(&[variant_to_u8(self.variant), strictness_to_u8(self.strictness)])?;
+        out.write_all(&(self.offset_table.len() as u32).to_le_bytes())?;
+        for entry in self.offset_table.iter() {
+            out.write_all(&entry.file_offset.to_le_bytes())?;
+            out.write_all(&entry.file_lenght.to_le_bytes())?;
+        }
+        out.write_all(&(self.warnings.len() as u32).to_le_bytes())?;
+        for warning in &self.warnings {
+            write_warning(out, warning)?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Write the parsed header of this archive to the index cache of the archive at ``archive_path`` (see
+    /// [`CPackIndex::cache_path`]), so it can be reopened with [`CPack::open_with_index_cache`] without parsing it again.
+    ///
+    /// The cache is a small binary file containing the table of content, the parse warnings and the SHA-256 of the
+    /// header, that is read again from this archive. This archive should be the one at ``archive_path``.
+    pub fn write_index_cache<P: AsRef<Path>>(&self, archive_path: P) -> Result<(), CPackError> {
+        let header_hash = {
+            let mut file = self.lock_file()?;
+            hash_header(&mut *file, self.header_len)?
+        };
+        let mut out = BufWriter::new(File::create(CPackIndex::cache_path(archive_path))?);
+        self.index().write_cache(&mut out, &header_hash)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+impl CPack<BufReader<File>> {
+    /// Open the archive at ``archive_path``, using its index cache (see [`CPack::write_index_cache`]) if there is one,
+    /// instead of parsing the header again. This is meant for tools that open the same big archives many times.
+    ///
+    /// The cache is used only if the archive has the same length and the same header as when it was written, which
+    /// only require reading the header, not scanning the padding between files. Otherwise, or if there is no usable
+    /// cache, the archive is parsed and a new cache is written. Failing to write the cache, like in a read-only
+    /// directory, isn't an error.
+    pub fn open_with_index_cache<P: AsRef<Path>>(archive_path: P) -> Result<Self, CPackError> {
+        Self::open_with_index_cache_and_options(archive_path, ParseOptions::default())
+    }
+
+    /// Same as [`CPack::open_with_index_cache`], using the given [`ParseOptions`]. A cache written by an archive parsed
+    /// with another variant or strictness isn't used. With [`Strictness::Strict`], the padding between files is still
+    /// scanned, as it isn't covered by the cache. With [`ParseOptions::skip_validation`], an existing cache is used,
+    /// but none is written, as the archive wasn't validated.
+    pub fn open_with_index_cache_and_options<P: AsRef<Path>>(archive_path: P, options: ParseOptions) -> Result<Self, CPackError> {
+        let archive_path = archive_path.as_ref();
+        let mut file = BufReader::new(File::open(archive_path)?);
+        let cached = fs::read(CPackIndex::cache_path(archive_path))
+            .ok()
+            .and_then(|data| read_index_cache(&data))
+            .filter(|(_, index)| index.variant == options.variant && index.strictness == options.strictness);
+        if let Some((header_hash, index)) = cached {
+            // failing to read the header, like if the archive was modified meanwhile, only mean the cache can't be used
+            if file.seek(SeekFrom::End(0))? == index.archive_len
+                && hash_header(&mut file, index.header_len).ok() == Some(header_hash)
+            {
+                let archive_len = index.archive_len;
+                let mut pack = CPack::from_index(file, index);
+                // the padding isn't covered by the hash of the header, so it is scanned again to stay as strict as
+                // parsing. If it isn't consistent anymore, the archive is parsed again to report it.
+                let padding_is_consistent = options.strictness < Strictness::Strict
+                    || options.skip_validation
+                    || pack.scan_padding(&mut *pack.lock_file()?, archive_len)?.0.is_none();
+                if padding_is_consistent {
+                    pack.set_read_limit(options.read_limit);
+                    pack.skip_validation = options.skip_validation;
+                    return Ok(pack);
+                };
+                file = BufReader::new(File::open(archive_path)?);
+            } else {
+                file.seek(SeekFrom::Start(0))?;
+            };
+        };
+        let skip_validation = options.skip_validation;
+        let pack = CPack::new_with_options(file, options)?;
+        if !skip_validation {
+            pack.write_index_cache(archive_path).ok();
+        };
+        Ok(pack)
+    }
+}
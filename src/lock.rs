@@ -0,0 +1,101 @@
+use crate::CPackError;
+use std::cell::{RefCell, RefMut};
+use std::ops::DerefMut;
+use std::sync::{Mutex, MutexGuard};
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl<F> Sealed for std::sync::Mutex<F> {}
+    impl<F> Sealed for std::cell::RefCell<F> {}
+    #[cfg(feature = "parking_lot")]
+    impl<F> Sealed for parking_lot::Mutex<F> {}
+}
+
+/// How the underlying reader of a [`crate::CPack`] is protected, so it can be read from a shared reference.
+/// This is the second type parameter of [`crate::CPack`], and is implemented for:
+///
+/// - [`std::sync::Mutex`], the default, so the archive can be shared between threads
+/// - [`parking_lot::Mutex`](https://docs.rs/parking_lot), with the ``parking_lot`` feature, that can't be poisoned
+/// - [`std::cell::RefCell`], for single-threaded programs (like in WASM) that don't want to pay for synchronization.
+///   As the reader is only borrowed while this crate read it, a borrow never fail unless an [`crate::IoObserver`]
+///   access the same archive, in which case [`CPackError::ArchiveLocked`] is returned.
+///
+/// There is no implementation without any interior mutability, as reading need to seek the reader through a shared
+/// reference. With exclusive access, a [`std::cell::RefCell`] is never contended.
+///
+/// This trait is sealed and can't be implemented outside of this crate. Only archives using the default
+/// [`std::sync::Mutex`] have [`crate::CPack::get_file`], as [`io_partition::PartitionMutex`] require it.
+pub trait LockStrategy<F>: sealed::Sealed {
+    /// The guard giving access to the reader while it is locked
+    type Guard<'a>: DerefMut<Target = F>
+    where
+        Self: 'a;
+
+    /// Wrap the reader
+    fn new(file: F) -> Self;
+
+    /// Lock the reader
+    fn lock(&self) -> Result<Self::Guard<'_>, CPackError>;
+
+    /// Return the reader. A poisoned lock doesn't prevent this.
+    fn into_inner(self) -> F;
+}
+
+impl<F> LockStrategy<F> for Mutex<F> {
+    type Guard<'a>
+        = MutexGuard<'a, F>
+    where
+        F: 'a;
+
+    fn new(file: F) -> Self {
+        Mutex::new(file)
+    }
+
+    fn lock(&self) -> Result<Self::Guard<'_>, CPackError> {
+        Mutex::lock(self).map_err(|_| CPackError::PoisonedLock)
+    }
+
+    fn into_inner(self) -> F {
+        Mutex::into_inner(self).unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<F> LockStrategy<F> for RefCell<F> {
+    type Guard<'a>
+        = RefMut<'a, F>
+    where
+        F: 'a;
+
+    fn new(file: F) -> Self {
+        RefCell::new(file)
+    }
+
+    fn lock(&self) -> Result<Self::Guard<'_>, CPackError> {
+        self.try_borrow_mut().map_err(|_| CPackError::ArchiveLocked)
+    }
+
+    fn into_inner(self) -> F {
+        RefCell::into_inner(self)
+    }
+}
+
+#[cfg(feature = "parking_lot")]
+impl<F> LockStrategy<F> for parking_lot::Mutex<F> {
+    type Guard<'a>
+        = parking_lot::MutexGuard<'a, F>
+    where
+        F: 'a;
+
+    fn new(file: F) -> Self {
+        parking_lot::Mutex::new(file)
+    }
+
+    fn lock(&self) -> Result<Self::Guard<'_>, CPackError> {
+        Ok(parking_lot::Mutex::lock(self))
+    }
+
+    fn into_inner(self) -> F {
+        parking_lot::Mutex::into_inner(self)
+    }
+}
@@ -0,0 +1,285 @@
+use crate::dump::RegionKind;
+use crate::extract::{extracted_name, is_plain_file_name};
+use crate::{CPack, CPackError, HeaderVariant, LockStrategy};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The name of the manifest written by [`CPack::extract_all_with_options`]
+pub(crate) const MANIFEST_FILE_NAME: &str = "MANIFEST";
+
+const MANIFEST_MAGIC: &str = "pmd_cpack-manifest 1";
+
+/// The maximum number of bytes of padding read at once when writing a manifest
+const PADDING_CHUNK_LEN: u64 = 0x10000;
+
+/// Where the bytes of a part of the rebuilt archive come from
+enum Source {
+    /// The header, as generated from the table of content
+    Header(Vec<u8>),
+    /// A file of the directory
+    File(String),
+    /// A repeated byte
+    Fill(u8),
+}
+
+/// A part of the rebuilt archive
+struct Segment {
+    start: u64,
+    len: u64,
+    source: Source,
+}
+
+/// The content of a manifest file
+struct Manifest {
+    variant: HeaderVariant,
+    length: u64,
+    /// The name of the file containing the raw header, if it isn't the one generated from the table of content
+    raw_header: Option<String>,
+    entries: Vec<(u64, u64)>,
+    /// The parts of the archive outside of the files
+    extra: Vec<Segment>,
+}
+
+fn variant_name(variant: HeaderVariant) -> &'static str {
+    match variant {
+        HeaderVariant::LittleEndian => "le",
+        HeaderVariant::BigEndian => "be",
+    }
+}
+
+/// Return the header of an archive with the given table of content, as written by [`crate::CPackWriter`]
+fn generate_header(variant: HeaderVariant, entries: &[(u64, u64)]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(16 + entries.len() * 8);
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&variant.u32_to_bytes(entries.len() as u32));
+    for (offset, length) in entries {
+        header.extend_from_slice(&variant.u32_to_bytes(*offset as u32));
+        header.extend_from_slice(&variant.u32_to_bytes(*length as u32));
+    }
+    header.extend_from_slice(&[0; 8]);
+    header
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return the byte the ``len`` bytes at ``start`` in ``file`` are all equal to, or None if they differ or if there
+    /// is none. They are read by chunks, stopping at the first different byte.
+    fn fill_byte(&self, file: &mut F, start: u64, len: u64) -> Result<Option<u8>, CPackError> {
+        file.seek(SeekFrom::Start(start))?;
+        let mut buffer = vec![0; len.min(PADDING_CHUNK_LEN) as usize];
+        let mut fill = None;
+        let mut position = start;
+        while position < start + len {
+            let chunk = &mut buffer[..(start + len - position).min(PADDING_CHUNK_LEN) as usize];
+            file.read_exact(chunk)?;
+            self.observer.on_read(position, chunk.len() as u64);
+            let byte = *fill.get_or_insert(chunk[0]);
+            if chunk.iter().any(|other| *other != byte) {
+                return Ok(None);
+            };
+            position += chunk.len() as u64;
+        }
+        Ok(fill)
+    }
+
+    /// Write a ``MANIFEST`` file in ``directory`` describing everything in the archive except the content of the files,
+    /// that are expected to be extracted to ``<id>.bin``. The padding that isn't made of a single repeated byte, the trailing
+    /// data and an unusual header are written to separate files next to it. They are copied by chunks, so huge trailing
+    /// data isn't loaded in memory.
+    pub(crate) fn write_manifest(&self, directory: &Path) -> Result<(), CPackError> {
+        let entries: Vec<(u64, u64)> = self.offset_table.iter().map(|info| (info.offset(), info.length())).collect();
+        let mut out = BufWriter::new(File::create(directory.join(MANIFEST_FILE_NAME))?);
+        writeln!(out, "{}", MANIFEST_MAGIC)?;
+        writeln!(out, "variant\t{}", variant_name(self.variant))?;
+        writeln!(out, "length\t{}", self.archive_len())?;
+
+        let mut file = self.lock_file()?;
+        for region in self.layout_regions() {
+            let len = region.end - region.start;
+            match region.kind {
+                RegionKind::Header => {
+                    file.seek(SeekFrom::Start(region.start))?;
+                    let mut header = vec![0; len as usize];
+                    file.read_exact(&mut header)?;
+                    self.observer.on_read(region.start, len);
+                    if header == generate_header(self.variant, &entries) {
+                        writeln!(out, "header\tgenerated")?;
+                    } else {
+                        fs::write(directory.join("header.bin"), header)?;
+                        writeln!(out, "header\theader.bin")?;
+                    };
+                }
+                RegionKind::File(_) => (),
+                RegionKind::Padding => match self.fill_byte(&mut file, region.start, len)? {
+                    Some(byte) => writeln!(out, "fill\t{}\t{}\t{:02x}", region.start, len, byte)?,
+                    None => {
+                        let name = format!("padding_{}.bin", region.start);
+                        let mut padding = BufWriter::new(File::create(directory.join(&name))?);
+                        file.seek(SeekFrom::Start(region.start))?;
+                        if io::copy(&mut (&mut *file).take(len), &mut padding)? != len {
+                            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                        };
+                        padding.flush()?;
+                        self.observer.on_read(region.start, len);
+                        writeln!(out, "data\t{}\t{}\t{}", region.start, len, name)?;
+                    }
+                },
+            };
+        }
+        for (offset, length) in &entries {
+            writeln!(out, "entry\t{}\t{}", offset, length)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// A line of a manifest file
+enum Line {
+    Variant(HeaderVariant),
+    Length(u64),
+    Header(Option<String>),
+    Entry(u64, u64),
+    Extra(Segment),
+}
+
+fn parse_line(line: &str) -> Option<Line> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    Some(match fields.as_slice() {
+        ["variant", "le"] => Line::Variant(HeaderVariant::LittleEndian),
+        ["variant", "be"] => Line::Variant(HeaderVariant::BigEndian),
+        ["length", length] => Line::Length(length.parse().ok()?),
+        ["header", "generated"] => Line::Header(None),
+        ["header", name] if is_plain_file_name(name) => Line::Header(Some(name.to_string())),
+        ["entry", offset, length] => Line::Entry(offset.parse().ok()?, length.parse().ok()?),
+        ["fill", start, len, byte] => Line::Extra(Segment {
+            start: start.parse().ok()?,
+            len: len.parse().ok()?,
+            source: Source::Fill(u8::from_str_radix(byte, 16).ok()?),
+        }),
+        ["data", start, len, name] if is_plain_file_name(name) => Line::Extra(Segment {
+            start: start.parse().ok()?,
+            len: len.parse().ok()?,
+            source: Source::File(name.to_string()),
+        }),
+        _ => return None,
+    })
+}
+
+fn parse_manifest<R: BufRead>(reader: R) -> Result<Manifest, CPackError> {
+    let mut lines = reader.lines();
+    if lines.next().transpose()?.as_deref() != Some(MANIFEST_MAGIC) {
+        return Err(CPackError::InvalidManifest(1));
+    };
+    let mut manifest = Manifest {
+        variant: HeaderVariant::default(),
+        length: 0,
+        raw_header: None,
+        entries: Vec::new(),
+        extra: Vec::new(),
+    };
+    for (line_nb, line) in (2..).zip(lines) {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        };
+        match parse_line(&line).ok_or(CPackError::InvalidManifest(line_nb))? {
+            Line::Variant(variant) => manifest.variant = variant,
+            Line::Length(length) => manifest.length = length,
+            Line::Header(raw_header) => manifest.raw_header = raw_header,
+            Line::Entry(offset, length) => manifest.entries.push((offset, length)),
+            Line::Extra(segment) => manifest.extra.push(segment),
+        };
+    }
+    Ok(manifest)
+}
+
+fn invalid_data(message: String) -> CPackError {
+    io::Error::new(io::ErrorKind::InvalidData, message).into()
+}
+
+/// Rebuild an archive extracted with [`crate::ExtractOptions::write_manifest`] from ``directory``, writing it to ``out``.
+///
+/// The ``<id>.bin`` files are placed at their original offset, and the padding, the trailing data and the header are
+/// restored from the manifest, so the result is identical to the original archive. As the layout is reproduced as-is,
+/// the files must have their original length, otherwise an error is returned. Use [`crate::CPackWriter`] to repack
+/// modified files.
+pub fn pack_from_manifest<P: AsRef<Path>, W: Write>(directory: P, out: &mut W) -> Result<(), CPackError> {
+    let directory = directory.as_ref();
+    let manifest = parse_manifest(BufReader::new(File::open(directory.join(MANIFEST_FILE_NAME))?))?;
+
+    let header = generate_header(manifest.variant, &manifest.entries);
+    let mut segments = manifest.extra;
+    segments.push(match manifest.raw_header {
+        Some(name) => Segment {
+            start: 0,
+            len: fs::metadata(directory.join(&name))?.len(),
+            source: Source::File(name),
+        },
+        None => Segment {
+            start: 0,
+            len: header.len() as u64,
+            source: Source::Header(header),
+        },
+    });
+    for (id, (offset, length)) in manifest.entries.iter().enumerate() {
+        let name = extracted_name(id);
+        let actual_length = fs::metadata(directory.join(&name))?.len();
+        if actual_length != *length {
+            return Err(invalid_data(format!(
+                "{} is {} bytes long, but the manifest expect {} bytes",
+                name, actual_length, length
+            )));
+        };
+        segments.push(Segment {
+            start: *offset,
+            len: *length,
+            source: Source::File(name),
+        });
+    }
+    segments.sort_by_key(|segment| segment.start);
+
+    // overlapping parts (like deduplicated files) are only written once
+    let mut position = 0;
+    for segment in segments {
+        let end = segment
+            .start
+            .checked_add(segment.len)
+            .ok_or_else(|| invalid_data(format!("the part of the manifest at {} end after the 64 bits limit", segment.start)))?;
+        if end > manifest.length {
+            return Err(invalid_data(format!(
+                "the part of the manifest at {} end after the {} bytes of the archive",
+                segment.start, manifest.length
+            )));
+        };
+        if end <= position {
+            continue;
+        };
+        if segment.start > position {
+            return Err(invalid_data(format!("the manifest doesn't describe the bytes at {}", position)));
+        };
+        let skip = position - segment.start;
+        match segment.source {
+            Source::Header(bytes) => out.write_all(&bytes[skip as usize..])?,
+            Source::Fill(byte) => {
+                io::copy(&mut io::repeat(byte).take(end - position), out)?;
+            }
+            Source::File(name) => {
+                let mut file = File::open(directory.join(&name))?;
+                file.seek(SeekFrom::Start(skip))?;
+                let copied = io::copy(&mut file.take(end - position), out)?;
+                if copied != end - position {
+                    return Err(invalid_data(format!("{} is shorter than expected", name)));
+                };
+            }
+        };
+        position = end;
+    }
+    if position != manifest.length {
+        return Err(invalid_data(format!(
+            "the manifest describe {} bytes, but the archive was {} bytes long",
+            position, manifest.length
+        )));
+    };
+    Ok(())
+}
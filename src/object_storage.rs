@@ -0,0 +1,75 @@
+use crate::range::{RangeFetch, RangeReader};
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read, Seek, SeekFrom};
+
+struct AsyncFetcher<G>(G);
+
+impl<G, Fut> RangeFetch for AsyncFetcher<G>
+where
+    G: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = io::Result<Vec<u8>>>,
+{
+    fn fetch(&mut self, start: u64, len: u64) -> io::Result<Vec<u8>> {
+        futures_executor::block_on((self.0)(start, len))
+    }
+}
+
+/// A remote file read through an asynchronous function returning the bytes at a given range, available with the ``async`` feature.
+///
+/// This allow to read archives stored in object storage (like S3 or GCS) with their SDK: the function typically send a
+/// ranged GET request for the object. Reads are grouped by block of at least 64 KiB, and the last block is cached.
+///
+/// The futures are run to completion on the thread doing the read, so reading must not be done from an asynchronous task.
+/// With tokio, use ``spawn_blocking``, so the futures still have access to the runtime.
+pub struct ObjectStorage<G> {
+    reader: RangeReader<AsyncFetcher<G>>,
+}
+
+impl<G, Fut> ObjectStorage<G>
+where
+    G: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = io::Result<Vec<u8>>>,
+{
+    /// Read a file of ``len`` bytes with ``fetch``, that should return the ``len`` bytes starting at ``start`` when
+    /// called with ``(start, len)``. It may only return less bytes at the end of the file.
+    pub fn new(len: u64, fetch: G) -> Self {
+        Self {
+            reader: RangeReader::new(AsyncFetcher(fetch), len),
+        }
+    }
+}
+
+impl<G> ObjectStorage<G> {
+    /// Return the length of the remote file
+    pub fn len(&self) -> u64 {
+        self.reader.len()
+    }
+
+    /// Return true if the remote file is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<G, Fut> Read for ObjectStorage<G>
+where
+    G: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = io::Result<Vec<u8>>>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<G> Seek for ObjectStorage<G> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl<G> fmt::Debug for ObjectStorage<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ObjectStorage({} bytes)", self.len())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{CPack, CPackError, LockStrategy};
+use std::fmt;
+use std::io::{Read, Seek};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Receive notifications about the IO performed by a [`CPack`], to collect metrics or throttle reads.
+///
+/// Every method have an empty default implementation. Reads done through the [`io_partition::PartitionMutex`]
+/// returned by [`CPack::get_file`] aren't reported, as they don't go through this crate.
+///
+/// With the ``metrics`` feature, the same events are also recorded with the ``metrics`` crate, whether an observer
+/// is set or not: the ``pmd_cpack_bytes_read_total`` and ``pmd_cpack_entries_opened_total`` counters and the
+/// ``pmd_cpack_lock_wait_seconds`` histogram. The ``pmd_cpack_parse_duration_seconds`` histogram record the time
+/// spent parsing the header when an archive is opened.
+pub trait IoObserver: Send + Sync {
+    /// Called after ``len`` bytes were read at ``offset`` in the archive
+    fn on_read(&self, _offset: u64, _len: u64) {}
+
+    /// Called after the lock of the underlying file was acquired, with the time spent waiting for it
+    fn on_lock_wait(&self, _wait: Duration) {}
+
+    /// Called when a reader over the file with the given id is created
+    fn on_entry_open(&self, _id: usize) {}
+}
+
+#[derive(Clone, Default)]
+/// The optional observer of a [`CPack`]
+pub(crate) struct ObserverSlot(Option<Arc<dyn IoObserver>>);
+
+impl ObserverSlot {
+    pub(crate) fn on_read(&self, offset: u64, len: u64) {
+        #[cfg(feature = "metrics")]
+        metrics::counter!("pmd_cpack_bytes_read_total").increment(len);
+        if let Some(observer) = &self.0 {
+            observer.on_read(offset, len);
+        }
+    }
+
+    pub(crate) fn on_lock_wait(&self, wait: Duration) {
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("pmd_cpack_lock_wait_seconds").record(wait.as_secs_f64());
+        if let Some(observer) = &self.0 {
+            observer.on_lock_wait(wait);
+        }
+    }
+
+    pub(crate) fn on_entry_open(&self, id: usize) {
+        #[cfg(feature = "metrics")]
+        metrics::counter!("pmd_cpack_entries_opened_total").increment(1);
+        if let Some(observer) = &self.0 {
+            observer.on_entry_open(id);
+        }
+    }
+}
+
+impl fmt::Debug for ObserverSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "Some(IoObserver)"),
+            None => write!(f, "None"),
+        }
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Set the observer notified of the IO performed by this archive, replacing the previous one
+    pub fn set_observer(&mut self, observer: Arc<dyn IoObserver>) {
+        self.observer = ObserverSlot(Some(observer));
+    }
+
+    /// Remove the observer, if any
+    pub fn clear_observer(&mut self) {
+        self.observer = ObserverSlot(None);
+    }
+
+    /// Lock the underlying file, reporting the time spent waiting to the observer
+    pub(crate) fn lock_file(&self) -> Result<L::Guard<'_>, CPackError> {
+        let start = Instant::now();
+        let file = self.file.lock()?;
+        self.observer.on_lock_wait(start.elapsed());
+        Ok(file)
+    }
+}
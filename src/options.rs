@@ -0,0 +1,69 @@
+use crate::CPackError;
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// The different flavour of cpack header
+///
+/// All of them share the same layout (4 zero bytes, the number of file, a table of offset and length, and 8 zero bytes),
+/// but differ on how the integers are encoded.
+pub enum HeaderVariant {
+    /// Little endian integers, as used by the Nintendo DS games like Explorers of Sky
+    #[default]
+    LittleEndian,
+    /// Big endian integers, as used on big endian platforms like the Wii (Adventure Squad WiiWare games)
+    BigEndian,
+}
+
+impl HeaderVariant {
+    pub(crate) fn read_u32<F: Read>(self, file: &mut F) -> Result<u32, CPackError> {
+        let mut buffer = [0; 4];
+        file.read_exact(&mut buffer)?;
+        Ok(self.u32_from_bytes(buffer))
+    }
+
+    pub(crate) fn u32_from_bytes(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Self::LittleEndian => u32::from_le_bytes(bytes),
+            Self::BigEndian => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub(crate) fn u32_to_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Self::LittleEndian => value.to_le_bytes(),
+            Self::BigEndian => value.to_be_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+/// How much deviation from the expected layout is tolerated while parsing
+pub enum Strictness {
+    /// Only read the table of content. The four first bytes and the terminator of the table of content aren't checked,
+    /// and files ending after the end of the archive are accepted (an error is only returned when they are accessed).
+    Lax,
+    /// Check the four first bytes, the terminator of the table of content and that every file is inside the archive
+    #[default]
+    Normal,
+    /// In addition to the [`Strictness::Normal`] checks, ensure that no file overlap the header
+    /// and that every padding byte outside of the files have the same value
+    Strict,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Options that change how a cpack file is parsed
+pub struct ParseOptions {
+    /// The header variant the file is expected to use
+    pub variant: HeaderVariant,
+    /// What is checked while parsing
+    pub strictness: Strictness,
+    /// The maximum number of bytes a single convenience read (like [`crate::CPack::read_file_to_vec`]) may load in memory,
+    /// or None for no limit. This protect against huge length in hostile archives.
+    pub read_limit: Option<u64>,
+    /// If true, the archive is trusted to be valid, like one just written by [`crate::CPackWriter`]: the table of content
+    /// is read at once, and only the four first bytes are checked. Files aren't checked to be inside the archive,
+    /// the terminator of the table of content isn't read, and the padding isn't scanned, so there are no warnings.
+    ///
+    /// This make opening many archives faster. Never use it with archives from an untrusted source.
+    pub skip_validation: bool,
+}
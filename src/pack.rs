@@ -0,0 +1,467 @@
+use crate::observer::ObserverSlot;
+use crate::writer::align_up;
+use crate::{ByteSnippet, CPackError, CPackIndex, HeaderVariant, LockStrategy, ParseOptions, ParseWarning, Strictness};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use io_partition::{Partition, PartitionMutex};
+
+/// The number of bytes captured before and after the problematic bytes in a [`ByteSnippet`]
+const SNIPPET_CONTEXT: u64 = 16;
+
+/// The number of bytes of padding read at once by [`CPack::scan_padding`]
+const PADDING_CHUNK_LEN: usize = 0x10000;
+
+/// Return the lowest offset of the non-empty files, or ``header_len`` if there is none. Empty files are ignored, as
+/// they may point anywhere, like at the start of the archive.
+fn first_data_offset(offset_table: &[EntryInfo], header_len: u64) -> u64 {
+    offset_table
+        .iter()
+        .filter(|index| index.file_lenght != 0)
+        .map(|index| index.file_offset as u64)
+        .min()
+        .unwrap_or(header_len)
+}
+
+/// Copy the ``len`` bytes at ``start`` and their surrounding. Any IO error result in a truncated snippet, as this is only used to report another error.
+fn capture_snippet<F: Read + Seek>(file: &mut F, start: u64, len: u64, file_len: u64) -> ByteSnippet {
+    let offset = start.saturating_sub(SNIPPET_CONTEXT).min(file_len);
+    let end = start.saturating_add(len).saturating_add(SNIPPET_CONTEXT).min(file_len);
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::Start(offset)).is_ok() {
+        let _ = file.take(end - offset).read_to_end(&mut bytes);
+    };
+    ByteSnippet { offset, bytes }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The position of a file inside a cpack archive, as stored in its table of content
+pub struct EntryInfo {
+    pub(crate) file_offset: u32,
+    pub(crate) file_lenght: u32,
+}
+
+impl EntryInfo {
+    /// Return the offset of the first byte of the file in the archive
+    pub fn offset(&self) -> u64 {
+        self.file_offset as u64
+    }
+
+    /// Return the length of the file, in bytes
+    pub fn length(&self) -> u64 {
+        self.file_lenght as u64
+    }
+
+    /// Return the offset of the first byte after the file in the archive
+    pub fn end(&self) -> u64 {
+        self.offset() + self.length()
+    }
+}
+
+#[derive(Debug)]
+/// A structure that represent a cpack file, used in pokemon mystery dungeon games
+///
+/// Those cpack file are archive that may contain multiple file, each file being identified by an id representing it's order of position in the file.
+///
+/// The reader is protected by a [`Mutex`] by default, so the archive can be shared between threads. Another
+/// [`LockStrategy`] can be used with [`CPack::new_with_lock`].
+pub struct CPack<F: Read + Seek, L: LockStrategy<F> = Mutex<F>> {
+    pub(crate) offset_table: Box<[EntryInfo]>,
+    pub(crate) header_len: u64,
+    pub(crate) data_start: u64,
+    pub(crate) archive_len: u64,
+    pub(crate) variant: HeaderVariant,
+    pub(crate) strictness: Strictness,
+    pub(crate) warnings: Vec<ParseWarning>,
+    pub(crate) read_limit: Option<u64>,
+    pub(crate) skip_validation: bool,
+    pub(crate) checksums: HashMap<usize, (u64, [u8; 32])>,
+    pub(crate) observer: ObserverSlot,
+    pub(crate) file: Arc<L>,
+    /// ``L`` only use ``F`` through [`LockStrategy`]. This doesn't change whether the archive is ``Send`` or ``Sync``.
+    pub(crate) reader: PhantomData<fn() -> F>,
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Create a CPack struct from a cpack file
+    pub fn new_from_file(file: F) -> Result<CPack<F>, CPackError> {
+        Self::new_with_options(file, ParseOptions::default())
+    }
+
+    /// Create a CPack struct from a cpack file, using the given [`ParseOptions`]
+    pub fn new_with_options(file: F, options: ParseOptions) -> Result<CPack<F>, CPackError> {
+        Ok(Self::new_counting_read(file, options)?.0)
+    }
+
+    /// Create a CPack struct from a cpack file of ``len`` bytes.
+    ///
+    /// The file is never seeked relative to its end, so this is useful for readers where it is slow or unsupported,
+    /// like network streams. ``len`` is trusted and used to check that every file is inside the archive.
+    pub fn new_with_len(file: F, len: u64) -> Result<CPack<F>, CPackError> {
+        Self::new_with_len_and_options(file, len, ParseOptions::default())
+    }
+
+    /// Same as [`CPack::new_with_len`], using the given [`ParseOptions`]
+    pub fn new_with_len_and_options(file: F, len: u64, options: ParseOptions) -> Result<CPack<F>, CPackError> {
+        Ok(Self::new_counting_read_with_len(file, Some(len), options)?.0)
+    }
+
+    /// get the file by an id, and return it as PartitionMutex. Return [`CPackError::EntryNotFound`] if it doesn't exist.
+    ///
+    /// If checksums were loaded with [`CPack::set_checksums`], the whole file is first read to verify it.
+    pub fn get_file(&self, id: usize) -> Result<PartitionMutex<F>, CPackError> {
+        let file_data = self.offset_table.get(id).ok_or(CPackError::EntryNotFound(id))?;
+        self.verify_entry(id)?;
+        self.observer.on_entry_open(id);
+        PartitionMutex::new(
+            self.file.clone(),
+            file_data.file_offset as u64,
+            file_data.file_lenght as u64,
+        ).map_err(CPackError::PartitionCreationError)
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Create a CPack struct from a cpack file, protected by the given [`LockStrategy`] instead of a [`Mutex`].
+    /// The strategy need to be specified, like in ``CPack::<_, RefCell<_>>::new_with_lock(file, options)``.
+    pub fn new_with_lock(file: F, options: ParseOptions) -> Result<Self, CPackError> {
+        Ok(Self::new_counting_read(file, options)?.0)
+    }
+
+    /// Create a CPack struct from a cpack file, also returning the number of bytes read while parsing
+    pub(crate) fn new_counting_read(file: F, options: ParseOptions) -> Result<(Self, u64), CPackError> {
+        Self::new_counting_read_with_len(file, None, options)
+    }
+
+    /// Create a CPack struct from a cpack file, whose length is queried by seeking to its end if it is None
+    fn new_counting_read_with_len(
+        file: F,
+        len: Option<u64>,
+        options: ParseOptions,
+    ) -> Result<(Self, u64), CPackError> {
+        let mut result = CPack{
+            offset_table: Box::default(),
+            header_len: 0,
+            data_start: 0,
+            archive_len: 0,
+            variant: options.variant,
+            strictness: options.strictness,
+            warnings: Vec::new(),
+            read_limit: options.read_limit,
+            skip_validation: options.skip_validation,
+            checksums: HashMap::new(),
+            observer: ObserverSlot::default(),
+            file: Arc::new(L::new(file)),
+            reader: PhantomData,
+        };
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let bytes_read = result.parse(len)?;
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("pmd_cpack_parse_duration_seconds").record(start.elapsed().as_secs_f64());
+        Ok((result, bytes_read))
+    }
+
+    /// Parse the header again from the underlying reader, with the same options, to take into account modifications
+    /// made to the archive since it was opened.
+    ///
+    /// If the new header can't be parsed, the error is returned and this archive is left unchanged.
+    /// The length of the archive is queried again by seeking to its end, even if it was opened with [`CPack::new_with_len`].
+    pub fn reload(&mut self) -> Result<(), CPackError> {
+        let mut reloaded = CPack {
+            offset_table: Box::default(),
+            header_len: 0,
+            data_start: 0,
+            archive_len: 0,
+            variant: self.variant,
+            strictness: self.strictness,
+            warnings: Vec::new(),
+            read_limit: self.read_limit,
+            skip_validation: self.skip_validation,
+            checksums: self.checksums.clone(),
+            observer: self.observer.clone(),
+            file: self.file.clone(),
+            reader: PhantomData,
+        };
+        reloaded.parse(None)?;
+        *self = reloaded;
+        Ok(())
+    }
+
+    /// Parse the header, returning the number of bytes read. The length of the archive is queried by seeking to its end if it is None.
+    fn parse(&mut self, len: Option<u64>) -> Result<u64, CPackError> {
+        let mut file = self.file.lock()?;
+
+        self.archive_len = match len {
+            Some(len) => len,
+            None => file.seek(SeekFrom::End(0))?,
+        };
+        let file_len = self.archive_len;
+
+        file.seek(SeekFrom::Start(0))?;
+        let mut first_four_bytes = [1; 4];
+        file.read_exact(&mut first_four_bytes)?;
+        if first_four_bytes != [0,0,0,0] {
+            if self.strictness >= Strictness::Normal {
+                return Err(CPackError::FourFirstByteNotZero(first_four_bytes, capture_snippet(&mut *file, 0, 4, file_len)));
+            };
+            self.warnings.push(ParseWarning::FourFirstByteNotZero(first_four_bytes));
+        };
+
+        let number_of_file = self.variant.read_u32(&mut *file)?;
+
+        if self.skip_validation {
+            // checked before allocating, so a corrupted number of file can't make us allocate gigabytes
+            let table_len = (number_of_file as u64)
+                .checked_mul(8)
+                .filter(|table_len| table_len.checked_add(16).is_some_and(|header_len| header_len <= file_len))
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            let mut table = vec![0; usize::try_from(table_len).map_err(|_| CPackError::SizeOverflow)?];
+            file.read_exact(&mut table)?;
+            let variant = self.variant;
+            let read_field = |bytes: &[u8]| variant.u32_from_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            self.offset_table = table
+                .chunks_exact(8)
+                .map(|entry| EntryInfo {
+                    file_offset: read_field(&entry[0..4]),
+                    file_lenght: read_field(&entry[4..8]),
+                })
+                .collect();
+            self.header_len = 8 + table.len() as u64 + 8;
+            self.data_start = first_data_offset(&self.offset_table, self.header_len);
+            return Ok(8 + table.len() as u64);
+        };
+
+        let mut offset_table = Vec::new();
+        for file_id in 0..number_of_file {
+            let file_offset = self.variant.read_u32(&mut *file)?;
+            let file_lenght = self.variant.read_u32(&mut *file)?;
+            let file_end = file_offset as u64 + file_lenght as u64;
+            if file_end > file_len {
+                if self.strictness >= Strictness::Normal {
+                    let entry_start = 8 + file_id as u64 * 8;
+                    return Err(CPackError::EndOfFileOutOfScope(file_id, file_end, file_len, capture_snippet(&mut *file, entry_start, 8, file_len)));
+                };
+                self.warnings.push(ParseWarning::EndOfFileOutOfScope(file_id));
+            }
+            offset_table.push(EntryInfo {
+                file_offset, file_lenght,
+            });
+        }
+        self.offset_table = offset_table.into_boxed_slice();
+
+        let mut buffer = [1; 8];
+        file.read_exact(&mut buffer)?;
+        if buffer != [0,0,0,0,0,0,0,0] {
+            if self.strictness >= Strictness::Normal {
+                let end_of_header = file.seek(SeekFrom::Current(0))?;
+                let snippet = capture_snippet(&mut *file, end_of_header - 8, 8, file_len);
+                return Err(CPackError::EndOfHeaderNotZero(end_of_header, buffer, snippet));
+            };
+            self.warnings.push(ParseWarning::EndOfHeaderNotZero(buffer));
+        }
+
+        self.header_len = file.seek(SeekFrom::Current(0))?;
+        self.data_start = first_data_offset(&self.offset_table, self.header_len);
+
+        let layout_warnings = self.layout_warnings(file_len);
+        let mut padding_bytes_read = 0;
+        if self.strictness >= Strictness::Strict {
+            for warning in &layout_warnings {
+                if let ParseWarning::EntryOverlapHeader(file_id) = warning {
+                    return Err(CPackError::EntryOverlapHeader(*file_id));
+                };
+            }
+            let (inconsistency, bytes_read) = self.scan_padding(&mut *file, file_len)?;
+            if let Some(ParseWarning::InconsistentPadding(offset, expected, found)) = inconsistency {
+                return Err(CPackError::InconsistentPadding(offset, expected, found));
+            };
+            padding_bytes_read = bytes_read;
+        };
+        self.warnings.extend(layout_warnings);
+        Ok(self.header_len + padding_bytes_read)
+    }
+
+    /// Look for oddities in the layout of the files that can be seen from the table of content alone: files
+    /// overlapping the header, and data after the last file.
+    fn layout_warnings(&self, file_len: u64) -> Vec<ParseWarning> {
+        let mut warnings = Vec::new();
+        for (file_id, index) in self.offset_table.iter().enumerate() {
+            if index.file_lenght != 0 && (index.file_offset as u64) < self.header_len {
+                warnings.push(ParseWarning::EntryOverlapHeader(file_id as u32));
+            };
+        }
+
+        let last_end = self.offset_table.iter()
+            .filter(|index| index.file_lenght != 0)
+            .map(EntryInfo::end)
+            .max()
+            .unwrap_or(self.header_len);
+        let expected_end = align_up(last_end, self.infer_alignment().entry);
+        if file_len > expected_end {
+            warnings.push(ParseWarning::TrailingData(expected_end, file_len - expected_end));
+        };
+        warnings
+    }
+
+    /// Read the bytes outside of the header and of the files, by chunks of [`PADDING_CHUNK_LEN`], returning a
+    /// [`ParseWarning::InconsistentPadding`] for the first one that differ from the previous ones, if any. Also return
+    /// the number of bytes read.
+    pub(crate) fn scan_padding(&self, file: &mut F, file_len: u64) -> Result<(Option<ParseWarning>, u64), CPackError> {
+        let mut ranges: Vec<(u64, u64)> = self.offset_table.iter()
+            .filter(|index| index.file_lenght != 0)
+            .map(|index| (index.offset(), index.end()))
+            .collect();
+        ranges.sort_unstable();
+        let mut buffer = Vec::new();
+        let mut bytes_read = 0;
+        let mut padding_byte = None;
+        let mut position = self.header_len;
+        for (start, end) in ranges.into_iter().chain(std::iter::once((file_len, file_len))) {
+            if start > position {
+                if buffer.is_empty() {
+                    buffer = vec![0; PADDING_CHUNK_LEN];
+                };
+                file.seek(SeekFrom::Start(position))?;
+                let mut gap_position = position;
+                while gap_position < start {
+                    let chunk_len = (start - gap_position).min(PADDING_CHUNK_LEN as u64) as usize;
+                    let chunk = match file.read(&mut buffer[..chunk_len]) {
+                        // the archive is shorter than the length it was opened with
+                        Ok(0) => return Ok((None, bytes_read)),
+                        Ok(read) => &buffer[..read],
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => return Err(err.into()),
+                    };
+                    for (chunk_offset, byte) in chunk.iter().enumerate() {
+                        match padding_byte {
+                            None => padding_byte = Some(*byte),
+                            Some(expected) if expected != *byte => {
+                                let offset = gap_position + chunk_offset as u64;
+                                bytes_read += chunk_offset as u64 + 1;
+                                return Ok((Some(ParseWarning::InconsistentPadding(offset, expected, *byte)), bytes_read));
+                            }
+                            Some(_) => (),
+                        }
+                    }
+                    bytes_read += chunk.len() as u64;
+                    gap_position += chunk.len() as u64;
+                }
+            };
+            position = position.max(end);
+        }
+        Ok((None, bytes_read))
+    }
+
+    /// Return the length of the whole archive, in bytes
+    pub fn archive_len(&self) -> u64 {
+        self.archive_len
+    }
+
+    /// Return the non-fatal oddities found while parsing the archive
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Return the number of file in the cpack archive
+    pub fn len(&self) -> usize {
+        self.offset_table.len()
+    }
+
+    /// Return true if the cpack archive is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the header variant this archive was parsed with
+    pub fn variant(&self) -> HeaderVariant {
+        self.variant
+    }
+
+    /// Return the number of bytes used by the header, including the table of content and its zero terminator
+    pub fn header_len(&self) -> u64 {
+        self.header_len
+    }
+
+    /// Return the offset of the first byte of file data in the archive (the lowest offset of the non-empty files in the
+    /// table of content).
+    ///
+    /// If the archive is empty, or contain only empty files, this is the end of the header.
+    pub fn data_start(&self) -> u64 {
+        self.data_start
+    }
+
+    /// Return the position of the file with the given id, or None if it doesn't exist
+    pub fn entry_info(&self, id: usize) -> Option<EntryInfo> {
+        self.offset_table.get(id).copied()
+    }
+
+    /// Return the position of every file, indexed by id, as stored in the table of content
+    pub fn entry_table(&self) -> &[EntryInfo] {
+        &self.offset_table
+    }
+
+    /// Return the underlying reader and the index it was parsed into.
+    ///
+    /// The reader is shared with the files returned by [`CPack::get_file`], so if one of them is still alive,
+    /// the archive is returned unchanged as the error. A lock poisoned by a panic while reading doesn't prevent this.
+    #[allow(clippy::result_large_err)]
+    pub fn into_inner(self) -> Result<(F, CPackIndex), Self> {
+        match Arc::try_unwrap(self.file) {
+            Ok(lock) => {
+                let file = lock.into_inner();
+                let index = CPackIndex {
+                    offset_table: self.offset_table,
+                    header_len: self.header_len,
+                    data_start: self.data_start,
+                    archive_len: self.archive_len,
+                    variant: self.variant,
+                    strictness: self.strictness,
+                    warnings: self.warnings,
+                };
+                Ok((file, index))
+            }
+            Err(file) => Err(CPack { file, ..self }),
+        }
+    }
+}
+
+impl<F: Read + Seek + Clone, L: LockStrategy<F>> CPack<F, L> {
+    /// Return a reader over the file with the given id, reading from its own clone of the underlying reader.
+    ///
+    /// The lock is only held while cloning the reader, so the returned readers can be read concurrently from multiple
+    /// thread without contention. This is intended for sources that are cheap to clone, like ``Cursor<Arc<[u8]>>``
+    /// for archives in memory: cloning a [`std::io::Cursor`] over a ``Vec<u8>`` copy the whole archive.
+    ///
+    /// Like [`CPack::get_file`], the file is first verified if checksums were loaded with [`CPack::set_checksums`].
+    pub fn get_file_cloned(&self, id: usize) -> Result<Partition<F>, CPackError> {
+        let info = *self.offset_table.get(id).ok_or(CPackError::EntryNotFound(id))?;
+        self.verify_entry(id)?;
+        let handle = self.lock_file()?.clone();
+        self.observer.on_entry_open(id);
+        Partition::new(handle, info.offset(), info.length()).map_err(CPackError::PartitionCreationError)
+    }
+}
+
+/*#[test]
+fn test_cpack_read() {
+    const some_value: [u8; 42] = [0,0,0,0, //0-the magic
+        2,0,0,0, //4-the number of element
+        32,0,0,0,5,0,0,0, //8-the offset and the lenght of the first element
+        37,0,0,0,5,0,0,0, //16-idem for the second element
+        0,0,0,0,0,0,0,0, //24-magic
+        104,101,108,108,111, //32-b"hello"
+        119,111,114,108,100, //37-b"world"
+    ];
+
+    let buf = std::io::Cursor::new(some_value);
+    let pack = CPack::new_from_file(buf).unwrap();
+    assert_eq!(pack.len(), 2);
+    let mut string_buffer = String::new();
+    pack.get_file(0).unwrap().read_to_string(&mut string_buffer);
+    assert_eq!(string_buffer, String::from("hello"));
+    pack.get_file(1).unwrap().read_to_string(&mut string_buffer);
+    assert_eq!(string_buffer, String::from("world"));
+}*/
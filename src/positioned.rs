@@ -0,0 +1,123 @@
+use crate::{CPack, CPackError};
+use io_partition::Partition;
+use positioned_io::{ReadAt, Size};
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+/// A cheaply clonable reader over any [`ReadAt`] source from the ``positioned-io`` crate (like a [`std::fs::File`] or
+/// a ``Vec<u8>``), where every clone has its own position. Available with the ``positioned-io`` feature.
+///
+/// Like [`crate::SharedFile`], clones can read the same source from multiple thread in parallel, without serializing
+/// on a lock, but the platform-specific positioned IO is left to ``positioned-io``.
+pub struct PositionedStorage<R> {
+    source: Arc<R>,
+    len: u64,
+    position: u64,
+}
+
+impl<R: ReadAt + Size> PositionedStorage<R> {
+    /// Wrap ``source``, querying its length. An error is returned if the length is unknown, use
+    /// [`PositionedStorage::with_len`] in this case.
+    pub fn new(source: R) -> io::Result<Self> {
+        match source.size()? {
+            Some(len) => Ok(Self::with_len(source, len)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the length of the source is unknown",
+            )),
+        }
+    }
+}
+
+impl<R: ReadAt> PositionedStorage<R> {
+    /// Wrap ``source``, that is ``len`` bytes long
+    pub fn with_len(source: R, len: u64) -> Self {
+        Self {
+            source: Arc::new(source),
+            len,
+            position: 0,
+        }
+    }
+}
+
+impl<R> PositionedStorage<R> {
+    /// Return the length of the source
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Return true if the source is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the wrapped source
+    pub fn source(&self) -> &R {
+        &self.source
+    }
+}
+
+impl<R> Clone for PositionedStorage<R> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source.clone(),
+            len: self.len,
+            position: self.position,
+        }
+    }
+}
+
+impl<R: ReadAt> Read for PositionedStorage<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.source.read_at(self.position, buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R> Seek for PositionedStorage<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.len, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let new_position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
+
+impl<R> fmt::Debug for PositionedStorage<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PositionedStorage({} bytes)", self.len)
+    }
+}
+
+impl<R: ReadAt> CPack<PositionedStorage<R>> {
+    /// Return a reader over the file with the given id that doesn't share a lock with the other readers of this archive.
+    ///
+    /// Unlike [`CPack::get_file`], readers returned by this function can be read concurrently from multiple thread.
+    /// Like it, the file is first verified if checksums were loaded with [`CPack::set_checksums`].
+    pub fn get_file_positioned(&self, id: usize) -> Result<Partition<PositionedStorage<R>>, CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        self.verify_entry(id)?;
+        let handle = self.lock_file()?.clone();
+        self.observer.on_entry_open(id);
+        Partition::new(handle, info.offset(), info.length()).map_err(CPackError::PartitionCreationError)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{Alignment, HeaderVariant, WriterProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The layout conventions of the cpack files of a known game, to write archives the game accept without having to
+/// find its padding rules first.
+///
+/// Only the layout of Explorers of Sky is known for now. The archives of the other games and regional releases weren't
+/// compared, and the written archives aren't verified to be byte-identical to those of the game.
+///
+/// Turn it into a [`WriterProfile`] with [`GamePreset::profile`] or [`From`].
+pub enum GamePreset {
+    /// Explorers of Sky. The files are padded to 16 bytes and the header to 64 bytes, with 0xFF, as found when writing
+    /// the first version of this crate. The header was then followed by zero bytes up to a 32 bytes boundary before
+    /// its 0xFF padding: this isn't reproduced, as mixed padding bytes are refused by [`crate::Strictness::Strict`].
+    ExplorersOfSky,
+}
+
+impl GamePreset {
+    /// Every known preset
+    pub const ALL: [GamePreset; 1] = [Self::ExplorersOfSky];
+
+    /// Return the settings to give to [`crate::CPackWriter`] to write an archive for this game
+    pub fn profile(self) -> WriterProfile {
+        match self {
+            Self::ExplorersOfSky => WriterProfile {
+                alignment: Alignment {
+                    header: 64,
+                    entry: 16,
+                },
+                padding_byte: 0xFF,
+                variant: HeaderVariant::LittleEndian,
+            },
+        }
+    }
+}
+
+impl From<GamePreset> for WriterProfile {
+    fn from(preset: GamePreset) -> Self {
+        preset.profile()
+    }
+}
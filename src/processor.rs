@@ -0,0 +1,75 @@
+use crate::CPackError;
+use std::fmt;
+use std::sync::Arc;
+
+/// Transform the files of an archive while they are extracted with [`crate::CPack::extract_all_with_options`], like
+/// decompressing PKDPX files or converting sprites, so the archive doesn't need to be read again afterward. The same
+/// processors can run the other way when creating an archive, see [`crate::CPackWriter::set_processors`].
+///
+/// It is also implemented for closures with the same signature as [`EntryProcessor::process`].
+pub trait EntryProcessor: Send + Sync {
+    /// Process the file with the given id before it is written. ``name`` is the name of the file that will be written in
+    /// the extraction directory, ``<id>.bin`` unless a previous processor changed it, and ``content`` its content.
+    /// Both can be modified. The name must stay a plain file name, without directory.
+    ///
+    /// When writing an archive, ``name`` is the source of the file instead, as described in
+    /// [`crate::CPackWriter::set_processors`].
+    fn process(&self, id: usize, name: &mut String, content: &mut Vec<u8>) -> Result<(), CPackError>;
+}
+
+impl<T> EntryProcessor for T
+where
+    T: Fn(usize, &mut String, &mut Vec<u8>) -> Result<(), CPackError> + Send + Sync,
+{
+    fn process(&self, id: usize, name: &mut String, content: &mut Vec<u8>) -> Result<(), CPackError> {
+        self(id, name, content)
+    }
+}
+
+#[derive(Clone, Default)]
+/// A list of [`EntryProcessor`] applied one after the other, each one receiving the output of the previous one.
+/// An empty chain leave the files untouched.
+pub struct ProcessorChain(Vec<Arc<dyn EntryProcessor>>);
+
+impl ProcessorChain {
+    /// Create an empty chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a processor at the end of the chain
+    pub fn then<P: EntryProcessor + 'static>(mut self, processor: P) -> Self {
+        self.push(Arc::new(processor));
+        self
+    }
+
+    /// Add a processor at the end of the chain. It may be shared with other chains.
+    pub fn push(&mut self, processor: Arc<dyn EntryProcessor>) {
+        self.0.push(processor);
+    }
+
+    /// Return the number of processor in the chain
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return true if there is no processor in the chain
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl EntryProcessor for ProcessorChain {
+    fn process(&self, id: usize, name: &mut String, content: &mut Vec<u8>) -> Result<(), CPackError> {
+        for processor in &self.0 {
+            processor.process(id, name, content)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ProcessorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProcessorChain({} processors)", self.0.len())
+    }
+}
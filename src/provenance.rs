@@ -0,0 +1,131 @@
+use crate::hash::{sha256_from_hex, to_hex};
+use crate::CPackError;
+use std::io::{BufRead, Write};
+
+const PROVENANCE_MAGIC: &str = "pmd_cpack-provenance 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Where a single file of an archive came from
+pub struct ProvenanceEntry {
+    /// The id of the file in the archive
+    pub id: usize,
+    /// The length of the file
+    pub length: u64,
+    /// The SHA-256 of the file content
+    pub sha256: [u8; 32],
+    /// A description of where the content came from, typically the loose file it was read from
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A record of how an archive was built, meant to be stored in a sidecar file next to it.
+///
+/// It is written as a simple text file: a magic line, a line with the name and version of the tool that built the archive,
+/// then a line per file with its id, length, SHA-256 and source separated by tabulations.
+pub struct Provenance {
+    /// The name and version of the tool that wrote the archive
+    pub tool: String,
+    /// The record of every file, ordered by id
+    pub entries: Vec<ProvenanceEntry>,
+}
+
+/// Escape the backslash, tabulation and new line characters, so the text fit in a single field of a line separated
+/// by tabulations. This is also used by [`crate::ArchiveRegistry`].
+pub(crate) fn escape_field(source: &str) -> String {
+    source
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Reverse [`escape_field`]
+pub(crate) fn unescape_field(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+impl Provenance {
+    /// Create an empty record, attributed to this version of this crate
+    pub fn new() -> Self {
+        Self {
+            tool: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Write the sidecar file to ``out``
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        writeln!(out, "{}", PROVENANCE_MAGIC)?;
+        writeln!(out, "{}", escape_field(&self.tool))?;
+        for entry in &self.entries {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                entry.id,
+                entry.length,
+                to_hex(&entry.sha256),
+                entry.source.as_deref().map(escape_field).unwrap_or_default()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Read a sidecar file previously written with [`Provenance::write`]
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, CPackError> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(PROVENANCE_MAGIC) {
+            return Err(CPackError::InvalidSidecar(1));
+        };
+        let tool = match lines.next().transpose()? {
+            Some(tool) => unescape_field(&tool),
+            None => return Err(CPackError::InvalidSidecar(2)),
+        };
+        let mut entries = Vec::new();
+        for (line_nb, line) in (3..).zip(lines) {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            };
+            entries.push(parse_entry(&line).ok_or(CPackError::InvalidSidecar(line_nb))?);
+        }
+        Ok(Self { tool, entries })
+    }
+}
+
+fn parse_entry(line: &str) -> Option<ProvenanceEntry> {
+    let mut fields = line.splitn(4, '\t');
+    let id = fields.next()?.parse().ok()?;
+    let length = fields.next()?.parse().ok()?;
+    let sha256 = sha256_from_hex(fields.next()?)?;
+    let source = match fields.next()? {
+        "" => None,
+        source => Some(unescape_field(source)),
+    };
+    Some(ProvenanceEntry {
+        id,
+        length,
+        sha256,
+        source,
+    })
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,84 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// The minimum number of bytes requested at once by a [`RangeReader`], so parsing the header doesn't need a request per integer
+const MIN_FETCH_LEN: u64 = 0x10000;
+
+/// A source able to return an arbitrary range of bytes of a file, like a server supporting range requests
+pub(crate) trait RangeFetch {
+    /// Return the ``len`` bytes starting at ``start``. Less bytes may be returned only at the end of the file.
+    fn fetch(&mut self, start: u64, len: u64) -> io::Result<Vec<u8>>;
+}
+
+#[derive(Debug)]
+/// A [`Read`] and [`Seek`] implementation over a [`RangeFetch`], that cache the last fetched range
+pub(crate) struct RangeReader<T> {
+    fetcher: T,
+    len: u64,
+    position: u64,
+    cache: Vec<u8>,
+    /// The offset of the first byte of ``cache``
+    cache_start: u64,
+}
+
+impl<T> RangeReader<T> {
+    /// Create a reader over a file of ``len`` bytes
+    pub(crate) fn new(fetcher: T, len: u64) -> Self {
+        Self {
+            fetcher,
+            len,
+            position: 0,
+            cache: Vec::new(),
+            cache_start: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl<T: RangeFetch> Read for RangeReader<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.len || buf.is_empty() {
+            return Ok(0);
+        };
+        let cache_end = self.cache_start + self.cache.len() as u64;
+        if self.position < self.cache_start || self.position >= cache_end {
+            let fetch_len = (buf.len() as u64).max(MIN_FETCH_LEN).min(self.len - self.position);
+            self.cache = self.fetcher.fetch(self.position, fetch_len)?;
+            self.cache_start = self.position;
+        };
+        let cache_offset = (self.position - self.cache_start) as usize;
+        let available = &self.cache[cache_offset.min(self.cache.len())..];
+        let read = available.len().min(buf.len());
+        buf[..read].copy_from_slice(&available[..read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<T> Seek for RangeReader<T> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.len, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let new_position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{CPack, CPackError, EntryInfo, LockStrategy};
+use io_partition::PartitionMutex;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+/// Files separated by at most this many bytes are read with a single read in [`CPack::read_files`]
+const MAX_COALESCED_GAP: u64 = 4096;
+
+/// Return an error equivalent to ``err``, an error returned while locking the archive, as [`CPackError`] can't be cloned
+fn copy_error(err: &CPackError) -> CPackError {
+    match err {
+        CPackError::PoisonedLock => CPackError::PoisonedLock,
+        CPackError::ArchiveLocked => CPackError::ArchiveLocked,
+        CPackError::IOError(err) => io::Error::new(err.kind(), err.to_string()).into(),
+        other => io::Error::other(other.to_string()).into(),
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return the maximum number of bytes a single convenience read may load in memory, if there is one
+    pub fn read_limit(&self) -> Option<u64> {
+        self.read_limit
+    }
+
+    /// Set the maximum number of bytes a single convenience read may load in memory, or remove it with None.
+    ///
+    /// Reads that would exceed it return a [`CPackError::ReadLimitExceeded`] without allocating anything.
+    pub fn set_read_limit(&mut self, limit: Option<u64>) {
+        self.read_limit = limit;
+    }
+
+    /// Return an error if reading ``len`` bytes at once would exceed the read limit
+    fn check_read_limit(&self, len: u64) -> Result<(), CPackError> {
+        match self.read_limit {
+            Some(limit) if len > limit => Err(CPackError::ReadLimitExceeded(len, limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Read the whole content of the file with the given id in memory
+    pub fn read_file_to_vec(&self, id: usize) -> Result<Vec<u8>, CPackError> {
+        let mut buffer = Vec::new();
+        self.read_file_with_buffer(id, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Same as [`CPack::read_file_to_vec`], but the content replace the content of ``buffer``.
+    ///
+    /// Reusing the same buffer when reading many files in a loop avoid allocating memory for each of them.
+    pub fn read_file_with_buffer(&self, id: usize, buffer: &mut Vec<u8>) -> Result<(), CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        self.check_read_limit(info.length())?;
+        let mut file = self.lock_file()?;
+        file.seek(SeekFrom::Start(info.offset()))?;
+        buffer.clear();
+        buffer.resize(info.length() as usize, 0);
+        file.read_exact(buffer)?;
+        self.observer.on_read(info.offset(), info.length());
+        self.verify_content(id, buffer)?;
+        Ok(())
+    }
+
+    /// Read ``len`` bytes of the file with the given id, starting at ``start`` bytes from the beginning of the file.
+    ///
+    /// Return an error if the range doesn't fit in the file. If checksums were loaded with [`CPack::set_checksums`], the
+    /// whole file is first read by chunk to verify it.
+    pub fn read_entry_range(&self, id: usize, start: u64, len: u64) -> Result<Vec<u8>, CPackError> {
+        let mut buffer = Vec::new();
+        self.read_entry_range_with_buffer(id, start, len, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Same as [`CPack::read_entry_range`], but the content replace the content of ``buffer``
+    pub fn read_entry_range_with_buffer(
+        &self,
+        id: usize,
+        start: u64,
+        len: u64,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        match start.checked_add(len) {
+            Some(end) if end <= info.length() => (),
+            _ => return Err(CPackError::RangeOutOfEntry(id, start, len)),
+        };
+        self.check_read_limit(len)?;
+        self.verify_entry(id)?;
+        let mut file = self.lock_file()?;
+        file.seek(SeekFrom::Start(info.offset() + start))?;
+        buffer.clear();
+        buffer.resize(len as usize, 0);
+        file.read_exact(buffer)?;
+        self.observer.on_read(info.offset() + start, len);
+        Ok(())
+    }
+
+    /// Return an iterator over the content of every file of the archive, in order of id, as returned by [`CPack::read_file_to_vec`].
+    ///
+    /// Each file is only read when the iterator reach it.
+    pub fn bytes_iter(&self) -> impl Iterator<Item = Result<Vec<u8>, CPackError>> + '_ {
+        (0..self.len()).map(move |id| self.read_file_to_vec(id))
+    }
+
+    /// Return an iterator over the content of the file with the given id, by chunk of ``chunk_size`` bytes (at least one).
+    /// Only the last chunk may be shorter.
+    ///
+    /// The lock on the archive is only held while a chunk is read, so other files can be read between two chunks.
+    /// The iterator stop after the first error. If checksums were loaded with [`CPack::set_checksums`], the whole file
+    /// is verified before the iterator is returned.
+    pub fn entry_chunks(&self, id: usize, chunk_size: usize) -> Result<EntryChunks<'_, F, L>, CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        let chunk_size = chunk_size.max(1);
+        self.check_read_limit(chunk_size as u64)?;
+        self.verify_entry(id)?;
+        Ok(EntryChunks {
+            pack: self,
+            info,
+            chunk_size,
+            position: 0,
+        })
+    }
+
+    /// Read the content of multiple files at once. The result are in the same order as ``ids``.
+    ///
+    /// The files are read by order of position in the archive, and those close to each other are read with a single seek and read,
+    /// which is way faster than reading them one by one when loading a lot of small files. A group of files is never bigger than the read limit,
+    /// and a file bigger than it result in a [`CPackError::ReadLimitExceeded`].
+    ///
+    /// If reading a group fail, like when one of its files end after the end of an archive parsed with
+    /// [`crate::Strictness::Lax`], its files are read again one by one, so only the failing ones return an error.
+    pub fn read_files(&self, ids: &[usize]) -> Vec<Result<Vec<u8>, CPackError>> {
+        let mut results: Vec<Result<Vec<u8>, CPackError>> =
+            ids.iter().map(|id| Err(CPackError::EntryNotFound(*id))).collect();
+
+        let mut requests: Vec<(usize, u64, u64)> = ids
+            .iter()
+            .enumerate()
+            .filter_map(|(request_nb, id)| {
+                let info = self.entry_info(*id)?;
+                if let Err(err) = self.check_read_limit(info.length()) {
+                    results[request_nb] = Err(err);
+                    return None;
+                };
+                Some((request_nb, info.offset(), info.end()))
+            })
+            .collect();
+        requests.sort_by_key(|(_, start, _)| *start);
+
+        let mut file = match self.lock_file() {
+            Ok(file) => file,
+            Err(err) => {
+                for (request_nb, _, _) in requests {
+                    results[request_nb] = Err(copy_error(&err));
+                }
+                return results;
+            }
+        };
+
+        let mut remaining = &requests[..];
+        while !remaining.is_empty() {
+            let run_start = remaining[0].1;
+            let mut run_end = remaining[0].2;
+            let mut run_len = 1;
+            while let Some((_, start, end)) = remaining.get(run_len) {
+                if *start > run_end.saturating_add(MAX_COALESCED_GAP)
+                    || self.check_read_limit(run_end.max(*end) - run_start).is_err()
+                {
+                    break;
+                };
+                run_end = run_end.max(*end);
+                run_len += 1;
+            }
+            let (run, rest) = remaining.split_at(run_len);
+            remaining = rest;
+
+            match self.read_locked(&mut *file, run_start, run_end) {
+                Ok(buffer) => {
+                    for (request_nb, start, end) in run {
+                        let content = &buffer[(start - run_start) as usize..(end - run_start) as usize];
+                        results[*request_nb] = self.verify_content(ids[*request_nb], content).map(|()| content.to_vec());
+                    }
+                }
+                Err(err) if run.len() == 1 => results[run[0].0] = Err(err),
+                Err(_) => {
+                    for (request_nb, start, end) in run {
+                        results[*request_nb] = self
+                            .read_locked(&mut *file, *start, *end)
+                            .and_then(|content| self.verify_content(ids[*request_nb], &content).map(|()| content));
+                    }
+                }
+            };
+        }
+        results
+    }
+
+    /// Read the bytes from ``start`` to ``end`` of ``file``, the locked reader of this archive
+    fn read_locked(&self, file: &mut F, start: u64, end: u64) -> Result<Vec<u8>, CPackError> {
+        let mut buffer = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut buffer)?;
+        self.observer.on_read(start, end - start);
+        Ok(buffer)
+    }
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Return an iterator over a reader for every file of the archive, in order of id, as returned by [`CPack::get_file`]
+    pub fn readers(&self) -> impl Iterator<Item = Result<PartitionMutex<F>, CPackError>> + '_ {
+        (0..self.len()).map(move |id| self.get_file(id))
+    }
+}
+
+/// An iterator over the content of a file by fixed size chunk, returned by [`CPack::entry_chunks`]
+pub struct EntryChunks<'a, F: Read + Seek, L: LockStrategy<F> = Mutex<F>> {
+    pack: &'a CPack<F, L>,
+    info: EntryInfo,
+    chunk_size: usize,
+    /// The offset in the file of the next chunk
+    position: u64,
+}
+
+impl<'a, F: Read + Seek, L: LockStrategy<F>> EntryChunks<'a, F, L> {
+    fn read_chunk(&self, len: u64) -> Result<Vec<u8>, CPackError> {
+        let mut file = self.pack.lock_file()?;
+        file.seek(SeekFrom::Start(self.info.offset() + self.position))?;
+        let mut buffer = vec![0; len as usize];
+        file.read_exact(&mut buffer)?;
+        self.pack.observer.on_read(self.info.offset() + self.position, len);
+        Ok(buffer)
+    }
+}
+
+impl<'a, F: Read + Seek, L: LockStrategy<F>> Iterator for EntryChunks<'a, F, L> {
+    type Item = Result<Vec<u8>, CPackError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.info.length() - self.position;
+        if remaining == 0 {
+            return None;
+        };
+        let len = remaining.min(self.chunk_size as u64);
+        let result = self.read_chunk(len);
+        self.position = match result {
+            Ok(_) => self.position + len,
+            Err(_) => self.info.length(),
+        };
+        Some(result)
+    }
+}
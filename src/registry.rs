@@ -0,0 +1,158 @@
+use crate::provenance::{escape_field, unescape_field};
+use crate::CPackError;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Component, Path};
+
+const REGISTRY_MAGIC: &str = "pmd_cpack-registry 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A known archive of a game, with the meaning of its files
+pub struct KnownArchive {
+    /// The path of the archive in the file system of the game, like ``EFFECT/effect.bin``. An archive match if its path
+    /// end with this one, ignoring the case, so it may also be just a file name.
+    pub path: String,
+    /// The number of file the archive contain, or None to match whatever the number of file
+    pub entry_count: Option<usize>,
+    /// What the archive contain, like ``the sprites of the effects``
+    pub description: String,
+    /// The name of the files of the archive, by id. Files without a known meaning are absent.
+    pub entries: BTreeMap<usize, String>,
+}
+
+impl KnownArchive {
+    /// Return the name of the file with the given id, if known
+    pub fn entry_name(&self, id: usize) -> Option<&str> {
+        self.entries.get(&id).map(String::as_str)
+    }
+
+    /// Return true if an archive at ``path``, with ``entry_count`` files, is this one
+    pub fn matches(&self, path: &Path, entry_count: usize) -> bool {
+        if self.entry_count.map(|count| count != entry_count).unwrap_or(false) {
+            return false;
+        };
+        let mut components = path
+            .components()
+            .rev()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy()),
+                _ => None,
+            });
+        self.path
+            .rsplit(['/', '\\'])
+            .filter(|name| !name.is_empty())
+            .all(|name| {
+                components
+                    .next()
+                    .map(|component| component.eq_ignore_ascii_case(name))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A list of known archives, mapping their files to human-readable names, like the sprite of a given effect.
+///
+/// It is loaded from a text file, so new games and archives can be described without changing this crate: a magic line,
+/// then for each archive a line ``archive``, path, number of file (or ``*``) and description, followed by a line per
+/// known file with its id and name, all separated by tabulations. Empty lines and lines starting with ``#`` are ignored.
+/// Backslashes, tabulations and new lines in the paths, descriptions and names are escaped as ``\\``, ``\t`` and ``\n``.
+///
+/// This crate doesn't ship any registry: it is up to the tools using it to provide one for the games they support.
+pub struct ArchiveRegistry {
+    archives: Vec<KnownArchive>,
+}
+
+impl ArchiveRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a registry written in the format described in [`ArchiveRegistry`]
+    pub fn read<R: BufRead>(reader: R) -> Result<Self, CPackError> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(REGISTRY_MAGIC) {
+            return Err(CPackError::InvalidRegistry(1));
+        };
+        let mut registry = Self::new();
+        for (line_nb, line) in (2..).zip(lines) {
+            let line = line?;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            };
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields.as_slice() {
+                ["archive", path, entry_count, description] => {
+                    let entry_count = match *entry_count {
+                        "*" => None,
+                        count => Some(count.parse().map_err(|_| CPackError::InvalidRegistry(line_nb))?),
+                    };
+                    registry.add(KnownArchive {
+                        path: unescape_field(path),
+                        entry_count,
+                        description: unescape_field(description),
+                        entries: BTreeMap::new(),
+                    });
+                }
+                [id, name] => {
+                    let id = id.parse().map_err(|_| CPackError::InvalidRegistry(line_nb))?;
+                    let archive = registry.archives.last_mut().ok_or(CPackError::InvalidRegistry(line_nb))?;
+                    archive.entries.insert(id, unescape_field(name));
+                }
+                _ => return Err(CPackError::InvalidRegistry(line_nb)),
+            };
+        }
+        Ok(registry)
+    }
+
+    /// Read the registry in the file at ``path``
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CPackError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Write the registry to ``out``, so it can be read back with [`ArchiveRegistry::read`]
+    pub fn write<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        writeln!(out, "{}", REGISTRY_MAGIC)?;
+        for archive in &self.archives {
+            let entry_count = match archive.entry_count {
+                Some(count) => count.to_string(),
+                None => "*".to_string(),
+            };
+            writeln!(
+                out,
+                "archive\t{}\t{}\t{}",
+                escape_field(&archive.path),
+                entry_count,
+                escape_field(&archive.description)
+            )?;
+            for (id, name) in &archive.entries {
+                writeln!(out, "{}\t{}", id, escape_field(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add an archive to the registry. When several archives match, the first one added is used.
+    pub fn add(&mut self, archive: KnownArchive) {
+        self.archives.push(archive);
+    }
+
+    /// Add every archive of ``other`` after the ones of this registry
+    pub fn extend(&mut self, other: ArchiveRegistry) {
+        self.archives.extend(other.archives);
+    }
+
+    /// Return every known archive
+    pub fn archives(&self) -> &[KnownArchive] {
+        &self.archives
+    }
+
+    /// Return the known archive at ``path`` with ``entry_count`` files, if any
+    pub fn find<P: AsRef<Path>>(&self, path: P, entry_count: usize) -> Option<&KnownArchive> {
+        self.archives
+            .iter()
+            .find(|archive| archive.matches(path.as_ref(), entry_count))
+    }
+}
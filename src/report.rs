@@ -0,0 +1,43 @@
+use crate::{Alignment, CPack, CPackError, HeaderVariant, ParseOptions, ParseWarning};
+use std::io::{Read, Seek};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A detailed description of a parsed archive, returned by [`CPack::parse_with_report`]
+pub struct ParseReport {
+    /// The header variant the archive was parsed with
+    pub variant: HeaderVariant,
+    /// The number of file in the archive
+    pub entry_count: usize,
+    /// The length of the header, including the table of content and its terminator
+    pub header_len: u64,
+    /// The offset of the first byte of file data
+    pub data_start: u64,
+    /// The length of the whole archive
+    pub archive_len: u64,
+    /// The number of bytes read from the archive while parsing
+    pub bytes_consumed: u64,
+    /// The alignment the archive seems to have been written with
+    pub alignment: Alignment,
+    /// The non-fatal oddities found while parsing
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Create a CPack struct from a cpack file, also returning a [`ParseReport`] describing its layout.
+    ///
+    /// This is useful to debug strange archives, or to know how to rebuild one identically.
+    pub fn parse_with_report(file: F, options: ParseOptions) -> Result<(CPack<F>, ParseReport), CPackError> {
+        let (pack, bytes_consumed) = Self::new_counting_read(file, options)?;
+        let report = ParseReport {
+            variant: pack.variant(),
+            entry_count: pack.len(),
+            header_len: pack.header_len(),
+            data_start: pack.data_start(),
+            archive_len: pack.archive_len(),
+            bytes_consumed,
+            alignment: pack.infer_alignment(),
+            warnings: pack.warnings().to_vec(),
+        };
+        Ok((pack, report))
+    }
+}
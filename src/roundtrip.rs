@@ -0,0 +1,70 @@
+use crate::{CPack, CPackError, CPackWriter, LockStrategy, WriterProfile};
+use std::io::{Read, Seek, SeekFrom};
+
+/// The number of bytes of the original archive compared at once by [`verify_roundtrip`]
+const COMPARE_CHUNK_LEN: usize = 0x10000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The result of [`verify_roundtrip`]
+pub struct RoundtripReport {
+    /// The length of the original archive
+    pub original_len: u64,
+    /// The length of the rebuilt archive
+    pub rebuilt_len: u64,
+    /// The offset of the first byte that differ between the two archives, or None if they are identical.
+    /// If one is a prefix of the other, this is the length of the shortest.
+    pub first_difference: Option<u64>,
+}
+
+impl RoundtripReport {
+    /// Return true if the rebuilt archive is identical to the original
+    pub fn is_identical(&self) -> bool {
+        self.first_difference.is_none()
+    }
+}
+
+/// Rebuild ``pack`` from its files with ``profile``, and compare the result with the original archive byte by byte.
+///
+/// Use the profile returned by [`CPack::infer_packer_profile`] to check that the archive can be rebuilt without loss.
+/// The rebuilt archive is kept in memory.
+pub fn verify_roundtrip<F: Read + Seek, L: LockStrategy<F>>(pack: &CPack<F, L>, profile: WriterProfile) -> Result<RoundtripReport, CPackError> {
+    let mut writer = CPackWriter::new(profile);
+    for id in 0..pack.len() {
+        writer.push(pack.read_file_to_vec(id)?);
+    }
+    let mut rebuilt = Vec::new();
+    writer.write(&mut rebuilt)?;
+
+    let mut file = pack.lock_file()?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut first_difference = None;
+    let mut buffer = vec![0; COMPARE_CHUNK_LEN];
+    let mut position = 0;
+    while position < rebuilt.len() {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        };
+        pack.observer.on_read(position as u64, read as u64);
+        let compared = read.min(rebuilt.len() - position);
+        if let Some(offset) = buffer[..compared]
+            .iter()
+            .zip(&rebuilt[position..position + compared])
+            .position(|(original_byte, rebuilt_byte)| original_byte != rebuilt_byte)
+        {
+            first_difference = Some((position + offset) as u64);
+            break;
+        };
+        position += read;
+    }
+
+    let rebuilt_len = rebuilt.len() as u64;
+    if first_difference.is_none() && pack.archive_len() != rebuilt_len {
+        first_difference = Some(pack.archive_len().min(rebuilt_len));
+    };
+    Ok(RoundtripReport {
+        original_len: pack.archive_len(),
+        rebuilt_len,
+        first_difference,
+    })
+}
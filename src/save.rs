@@ -0,0 +1,85 @@
+use crate::CPackError;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of temporary file created by this process, to give each one a different name
+static TEMPORARY_COUNT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Default)]
+/// Options used when saving an archive to a path
+pub struct SaveOptions {
+    /// If set, an existing file at the destination is first copied to a file with this suffix appended to its name (like ``.bak``)
+    pub backup_suffix: Option<String>,
+}
+
+impl SaveOptions {
+    /// Return options that backup an existing destination to ``<name>.bak``
+    pub fn with_backup() -> Self {
+        Self {
+            backup_suffix: Some(".bak".to_string()),
+        }
+    }
+
+    /// Copy ``path`` to its backup location, if a backup is wanted and ``path`` exist
+    fn backup(&self, path: &Path) -> io::Result<()> {
+        if let Some(suffix) = &self.backup_suffix {
+            if path.exists() {
+                let mut backup_path = path.as_os_str().to_owned();
+                backup_path.push(suffix);
+                fs::copy(path, backup_path)?;
+            };
+        };
+        Ok(())
+    }
+}
+
+/// Return the path of the temporary file used to atomically replace ``path``. It is in the same directory, so the rename can't cross file systems.
+///
+/// The name start with a dot and end with ``.tmp``, and is different for every call, even from several threads saving to the same path.
+fn temporary_path(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the path to save to doesn't have a file name",
+        )
+    })?;
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(file_name);
+    let count = TEMPORARY_COUNT.fetch_add(1, Ordering::Relaxed);
+    temporary_name.push(format!(".{}.{}.tmp", std::process::id(), count));
+    Ok(path.with_file_name(temporary_name))
+}
+
+/// Call ``write`` with a temporary file next to ``path``, then rename it over ``path`` once everything is written and synced.
+///
+/// If anything fail, the temporary file is removed and ``path`` is left untouched. The backup, if any, is done before the rename.
+pub(crate) fn save_atomic<P, W>(path: P, options: &SaveOptions, write: W) -> Result<(), CPackError>
+where
+    P: AsRef<Path>,
+    W: FnOnce(&mut BufWriter<File>) -> Result<(), CPackError>,
+{
+    let path = path.as_ref();
+    let temporary = temporary_path(path)?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temporary)?;
+
+    let result = (|| {
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+        drop(writer);
+        options.backup(path)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    };
+    result
+}
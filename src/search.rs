@@ -0,0 +1,61 @@
+use crate::{CPack, CPackError, LockStrategy};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// The number of bytes read at once by [`CPack::search`]
+const SEARCH_CHUNK_LEN: usize = 0x10000;
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Find every occurrence of ``pattern`` in the files of the archive, returning the id of the file and the offset
+    /// of the occurrence in it, ordered by id then by offset. Overlapping occurrences are all reported.
+    ///
+    /// The files are read by chunk, so the memory used doesn't depend on the size of the files. An empty pattern match nothing.
+    /// If checksums were loaded with [`CPack::set_checksums`], each file is verified before being searched.
+    pub fn search(&self, pattern: &[u8]) -> Result<Vec<(usize, u64)>, CPackError> {
+        let mut result = Vec::new();
+        if pattern.is_empty() {
+            return Ok(result);
+        };
+        for (id, info) in self.offset_table.iter().enumerate() {
+            if info.length() < pattern.len() as u64 {
+                continue;
+            };
+            self.verify_entry(id)?;
+            let mut file = self.lock_file()?;
+            file.seek(SeekFrom::Start(info.offset()))?;
+            let mut reader = (&mut *file).take(info.length());
+            let mut buffer = Vec::with_capacity(SEARCH_CHUNK_LEN + pattern.len());
+            // the offset in the file of the first byte of the buffer
+            let mut buffer_start = 0;
+            loop {
+                let kept_len = buffer.len();
+                buffer.resize(kept_len + SEARCH_CHUNK_LEN, 0);
+                let read = match reader.read(&mut buffer[kept_len..]) {
+                    Ok(read) => read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                        buffer.truncate(kept_len);
+                        continue;
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                buffer.truncate(kept_len + read);
+                if read == 0 {
+                    break;
+                };
+                // the kept bytes are shorter than the pattern, so no occurrence is reported twice
+                for (position, window) in buffer.windows(pattern.len()).enumerate() {
+                    if window == pattern {
+                        result.push((id, buffer_start + position as u64));
+                    };
+                }
+                let drained = buffer.len().saturating_sub(pattern.len() - 1);
+                buffer.drain(..drained);
+                buffer_start += drained as u64;
+            }
+            if buffer_start + buffer.len() as u64 != info.length() {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            };
+            self.observer.on_read(info.offset(), info.length());
+        }
+        Ok(result)
+    }
+}
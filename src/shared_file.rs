@@ -0,0 +1,101 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+/// ``FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE``
+#[cfg(windows)]
+const SHARE_ALL: u32 = 0x1 | 0x2 | 0x4;
+
+/// Open the file at ``path`` for reading, without preventing other programs from modifying, deleting or replacing it.
+///
+/// On Windows, files opened with [`File::open`] can't be deleted or renamed over while open, so a program keeping
+/// an archive open (like a GUI) would make saving it with [`crate::CPackWriter::save_atomic`] fail. This open it with
+/// every share mode. On Unix, this is the same as [`File::open`], as files are never locked this way.
+pub fn open_shared<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    shared_options().open(path)
+}
+
+/// Return the options used by [`open_shared`], to open a file for reading with every share mode
+pub(crate) fn shared_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        options.share_mode(SHARE_ALL);
+    }
+    options
+}
+
+#[derive(Debug, Clone)]
+/// A cheaply clonable handle to a file, where every clone has its own position.
+///
+/// Reads use positioned IO (``seek_read`` on Windows, ``read_at`` on Unix), so clones can read the same file from
+/// multiple thread in parallel, without serializing on a lock like the default ``CPack<File>`` does. Use
+/// [`crate::CPack::get_file_cloned`] to get such readers over the files of an archive.
+pub struct SharedFile {
+    file: Arc<File>,
+    position: u64,
+}
+
+impl SharedFile {
+    /// Wrap an opened file
+    pub fn new(file: File) -> Self {
+        Self {
+            file: Arc::new(file),
+            position: 0,
+        }
+    }
+
+    /// Open the file at the given path for reading, with [`open_shared`]
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(open_shared(path)?))
+    }
+
+    #[cfg(unix)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::unix::fs::FileExt;
+        self.file.read_at(buf, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        use std::os::windows::fs::FileExt;
+        self.file.seek_read(buf, offset)
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.read_at(buf, self.position)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.file.metadata()?.len(), offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let new_position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{CPack, CPackError, EntryInfo, HeaderVariant, ParseOptions};
+use std::io::Cursor;
+
+#[derive(Debug, Clone, Copy)]
+/// A cpack archive stored in a ``&'static [u8]``, like one embedded with [`include_bytes`].
+///
+/// Unlike [`CPack`], there is no reader nor lock: the files are returned as sub-slices of the archive, that live as
+/// long as the program. The header is checked once when created, then the accessors read the table of content directly.
+/// They are ``const fn``, so they can be used to initialize constants.
+pub struct CPackStatic {
+    data: &'static [u8],
+    variant: HeaderVariant,
+    len: usize,
+}
+
+/// Read the integer at ``offset`` in ``data``, or return None if it isn't in ``data``
+const fn read_u32(data: &[u8], variant: HeaderVariant, offset: usize) -> Option<u32> {
+    if offset + 4 > data.len() {
+        return None;
+    };
+    let bytes = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+    Some(match variant {
+        HeaderVariant::LittleEndian => u32::from_le_bytes(bytes),
+        HeaderVariant::BigEndian => u32::from_be_bytes(bytes),
+    })
+}
+
+impl CPackStatic {
+    /// Check the header of the archive in ``data``, and return it
+    pub fn from_static(data: &'static [u8]) -> Result<Self, CPackError> {
+        Self::from_static_with_options(data, ParseOptions::default())
+    }
+
+    /// Same as [`CPackStatic::from_static`], using the given [`ParseOptions`]. With [`crate::Strictness::Lax`], files
+    /// that end after the end of the archive are accepted, but [`CPackStatic::get`] return None for them.
+    pub fn from_static_with_options(data: &'static [u8], options: ParseOptions) -> Result<Self, CPackError> {
+        let pack = CPack::new_with_options(Cursor::new(data), options)?;
+        Ok(Self {
+            data,
+            variant: pack.variant(),
+            len: pack.len(),
+        })
+    }
+
+    /// Return the whole archive
+    pub const fn as_bytes(&self) -> &'static [u8] {
+        self.data
+    }
+
+    /// Return the header variant of the archive
+    pub const fn variant(&self) -> HeaderVariant {
+        self.variant
+    }
+
+    /// Return the number of file in the archive
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true if the archive is empty
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the position of the file with the given id, or None if it doesn't exist
+    pub const fn entry_info(&self, id: usize) -> Option<EntryInfo> {
+        if id >= self.len {
+            return None;
+        };
+        let file_offset = match read_u32(self.data, self.variant, 8 + id * 8) {
+            Some(offset) => offset,
+            None => return None,
+        };
+        let file_lenght = match read_u32(self.data, self.variant, 8 + id * 8 + 4) {
+            Some(length) => length,
+            None => return None,
+        };
+        Some(EntryInfo {
+            file_offset,
+            file_lenght,
+        })
+    }
+
+    /// Return the content of the file with the given id, or None if it doesn't exist or isn't inside the archive
+    pub const fn get(&self, id: usize) -> Option<&'static [u8]> {
+        let info = match self.entry_info(id) {
+            Some(info) => info,
+            None => return None,
+        };
+        if info.file_offset as u64 + info.file_lenght as u64 > self.data.len() as u64 {
+            return None;
+        };
+        let (_, after_start) = self.data.split_at(info.file_offset as usize);
+        let (content, _) = after_start.split_at(info.file_lenght as usize);
+        Some(content)
+    }
+
+    /// Return an iterator over the content of every file of the archive, in order of id.
+    /// Files that aren't inside the archive are returned as empty slices.
+    pub fn iter(&self) -> impl Iterator<Item = &'static [u8]> + '_ {
+        (0..self.len).map(move |id| self.get(id).unwrap_or(&[]))
+    }
+}
@@ -0,0 +1,150 @@
+//! [proptest](https://docs.rs/proptest) strategies generating archives, available with the ``proptest`` feature
+//!
+//! # Examples
+//! ```
+//! use pmd_cpack::strategy::valid_archive;
+//! use pmd_cpack::{CPack, ParseOptions};
+//! use proptest::prelude::*;
+//!
+//! proptest!(|(archive in valid_archive(8, 64))| {
+//!     let options = ParseOptions { variant: archive.profile.variant, ..Default::default() };
+//!     let pack = CPack::new_with_options(std::io::Cursor::new(archive.bytes), options).unwrap();
+//!     prop_assert_eq!(pack.len(), archive.files.len());
+//! });
+//! ```
+
+use crate::{Alignment, CPackWriter, HeaderVariant, WriterProfile};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+/// The biggest alignment generated, as the shift of a power of two
+const MAX_ALIGNMENT_SHIFT: u32 = 11;
+
+/// Generate the content of a file, of at most ``max_len`` bytes
+pub fn file_data(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..=max_len)
+}
+
+/// Generate the content of at most ``max_count`` files, of at most ``max_len`` bytes each
+pub fn files(max_count: usize, max_len: usize) -> impl Strategy<Value = Vec<Vec<u8>>> {
+    vec(file_data(max_len), 0..=max_count)
+}
+
+/// Generate an alignment, whose values are power of two up to 0x800
+pub fn alignment() -> impl Strategy<Value = Alignment> {
+    (0..=MAX_ALIGNMENT_SHIFT, 0..=MAX_ALIGNMENT_SHIFT).prop_map(|(header, entry)| Alignment {
+        header: 1 << header,
+        entry: 1 << entry,
+    })
+}
+
+/// Generate a profile, with any alignment, padding byte and header variant
+pub fn writer_profile() -> impl Strategy<Value = WriterProfile> {
+    (alignment(), any::<u8>(), any::<bool>()).prop_map(|(alignment, padding_byte, big_endian)| WriterProfile {
+        alignment,
+        padding_byte,
+        variant: if big_endian {
+            HeaderVariant::BigEndian
+        } else {
+            HeaderVariant::LittleEndian
+        },
+    })
+}
+
+#[derive(Debug, Clone)]
+/// An archive generated by [`valid_archive`], with what was used to write it
+pub struct GeneratedArchive {
+    /// The content of the files, in order
+    pub files: Vec<Vec<u8>>,
+    /// The profile the archive was written with
+    pub profile: WriterProfile,
+    /// The written archive
+    pub bytes: Vec<u8>,
+}
+
+/// Write the given files with the given profile
+fn write_archive(files: Vec<Vec<u8>>, profile: WriterProfile) -> GeneratedArchive {
+    let mut writer = CPackWriter::new(profile);
+    for file in &files {
+        writer.push(file.clone());
+    }
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).expect("generated archives are small enough to be written");
+    GeneratedArchive { files, profile, bytes }
+}
+
+/// Generate a valid archive of at most ``max_count`` files of at most ``max_len`` bytes, written with any profile
+pub fn valid_archive(max_count: usize, max_len: usize) -> impl Strategy<Value = GeneratedArchive> {
+    (files(max_count, max_len), writer_profile()).prop_map(|(files, profile)| write_archive(files, profile))
+}
+
+#[derive(Debug, Clone)]
+/// A modification turning a valid archive into a probably invalid one
+pub enum Corruption {
+    /// Xor the byte at the given index with a non-zero mask
+    FlipByte(Index, u8),
+    /// Cut the archive at the given index
+    Truncate(Index),
+    /// Overwrite the number of file with the given value, keeping the endianness
+    SetCount(u32),
+    /// Add the given bytes at the end of the archive
+    Append(Vec<u8>),
+}
+
+impl Corruption {
+    /// Apply this corruption to an archive written with the given header variant
+    pub fn apply(&self, bytes: &mut Vec<u8>, variant: HeaderVariant) {
+        match self {
+            Self::FlipByte(index, mask) => {
+                if !bytes.is_empty() {
+                    let index = index.index(bytes.len());
+                    bytes[index] ^= mask;
+                };
+            }
+            Self::Truncate(index) => {
+                if !bytes.is_empty() {
+                    let len = index.index(bytes.len());
+                    bytes.truncate(len);
+                };
+            }
+            Self::SetCount(count) => {
+                if bytes.len() >= 8 {
+                    bytes[4..8].copy_from_slice(&variant.u32_to_bytes(*count));
+                };
+            }
+            Self::Append(extra) => bytes.extend_from_slice(extra),
+        }
+    }
+}
+
+/// Generate a [`Corruption`]
+pub fn corruption() -> impl Strategy<Value = Corruption> {
+    prop_oneof![
+        (any::<Index>(), 1..=u8::MAX).prop_map(|(index, mask)| Corruption::FlipByte(index, mask)),
+        any::<Index>().prop_map(Corruption::Truncate),
+        prop_oneof![any::<u32>(), (u32::MAX - 16)..=u32::MAX].prop_map(Corruption::SetCount),
+        vec(any::<u8>(), 1..=64).prop_map(Corruption::Append),
+    ]
+}
+
+/// Generate the bytes of an archive that was valid before a single [`Corruption`] was applied to it.
+///
+/// The result may still be valid, depending on the corruption.
+pub fn near_valid_archive(max_count: usize, max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    (valid_archive(max_count, max_len), corruption()).prop_map(|(archive, corruption)| {
+        let mut bytes = archive.bytes;
+        corruption.apply(&mut bytes, archive.profile.variant);
+        bytes
+    })
+}
+
+/// Generate a little endian header claiming to contain close to [`u32::MAX`] files, followed by a few random bytes
+pub fn max_count_header() -> impl Strategy<Value = Vec<u8>> {
+    ((u32::MAX - 16)..=u32::MAX, vec(any::<u8>(), 0..=64)).prop_map(|(count, rest)| {
+        let mut bytes = vec![0; 4];
+        bytes.extend_from_slice(&count.to_le_bytes());
+        bytes.extend_from_slice(&rest);
+        bytes
+    })
+}
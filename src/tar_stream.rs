@@ -0,0 +1,76 @@
+use crate::extract::extracted_name;
+use crate::{CPack, CPackError, CPackWriter, LockStrategy, WriterProfile};
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Write every file of the archive to ``writer`` as a tar stream, available with the ``tar`` feature.
+    ///
+    /// Each file is named ``<id>.bin``, like with [`CPack::extract_all`]. The modification time and owner are left
+    /// to 0, so the same archive always produce the same tar stream. Files are read one at a time, and nothing is written to disk.
+    pub fn extract_to_tar<W: Write>(&self, writer: W) -> Result<(), CPackError> {
+        let mut builder = tar::Builder::new(writer);
+        for id in 0..self.len() {
+            let content = self.read_file_to_vec(id)?;
+            let mut header = tar::Header::new_ustar();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            builder.append_data(&mut header, extracted_name(id), content.as_slice())?;
+        }
+        builder.finish()?;
+        Ok(())
+    }
+}
+
+/// Return the id a file of a tar stream is added as: its name without extension, parsed as a number
+fn tar_entry_id(path: &Path) -> Option<usize> {
+    path.file_stem()?.to_str()?.parse().ok()
+}
+
+/// Read a tar stream of files named after their id (like ``0.bin``, ``1.bin``...) and return a [`CPackWriter`] containing
+/// them, to be written with the default [`WriterProfile`]. Available with the ``tar`` feature.
+///
+/// This is the reverse of [`CPack::extract_to_tar`]. The files don't need to be in order in the stream, and their
+/// extension and directory are ignored. Directories are skipped. The name of each file is recorded as its source,
+/// for [`CPackWriter::write_with_provenance`].
+pub fn pack_from_tar<R: Read>(reader: R) -> Result<CPackWriter<'static>, CPackError> {
+    pack_from_tar_with_profile(reader, WriterProfile::default())
+}
+
+/// Same as [`pack_from_tar`], but the returned [`CPackWriter`] will use the given profile
+pub fn pack_from_tar_with_profile<R: Read>(
+    reader: R,
+    profile: WriterProfile,
+) -> Result<CPackWriter<'static>, CPackError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        };
+        let path = entry.path()?.into_owned();
+        let name = path.display().to_string();
+        let id = match tar_entry_id(&path) {
+            Some(id) if !files.contains_key(&id) => id,
+            _ => return Err(CPackError::InvalidTarEntry(name)),
+        };
+        // the size is read from the stream, so it isn't trusted to preallocate the content
+        if entry.size() > u32::MAX as u64 {
+            return Err(CPackError::OffsetOverflow(entry.size()));
+        };
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        files.insert(id, (content, name));
+    }
+    let mut writer = CPackWriter::new(profile);
+    for (expected_id, (id, (content, name))) in files.into_iter().enumerate() {
+        if id != expected_id {
+            return Err(CPackError::MissingTarEntry(expected_id));
+        };
+        writer.push_with_source(content, name);
+    }
+    Ok(writer)
+}
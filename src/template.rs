@@ -0,0 +1,90 @@
+use crate::{CPack, CPackError, HeaderVariant, LockStrategy};
+use std::io::{Read, Seek, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The binary template languages [`CPack::write_template`] can output
+pub enum TemplateFormat {
+    /// A Kaitai Struct definition, in the ``.ksy`` yaml format
+    Kaitai,
+    /// An 010 Editor binary template, in the ``.bt`` format
+    Editor010,
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Write a template describing the layout of this archive in the given language, with the position of every file filled in.
+    ///
+    /// This allow to open the archive in an hex editor or a Kaitai Struct tool with its structure already annotated.
+    pub fn write_template<W: Write>(&self, out: &mut W, format: TemplateFormat) -> Result<(), CPackError> {
+        match format {
+            TemplateFormat::Kaitai => self.write_kaitai(out),
+            TemplateFormat::Editor010 => self.write_010(out),
+        }
+    }
+
+    fn write_kaitai<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        let endian = match self.variant() {
+            HeaderVariant::LittleEndian => "le",
+            HeaderVariant::BigEndian => "be",
+        };
+        writeln!(out, "meta:")?;
+        writeln!(out, "  id: cpack")?;
+        writeln!(out, "  endian: {}", endian)?;
+        writeln!(out, "seq:")?;
+        writeln!(out, "  - id: magic")?;
+        writeln!(out, "    size: 4")?;
+        writeln!(out, "  - id: file_count")?;
+        writeln!(out, "    type: u4")?;
+        writeln!(out, "  - id: table_of_content")?;
+        writeln!(out, "    type: toc_entry")?;
+        writeln!(out, "    repeat: expr")?;
+        writeln!(out, "    repeat-expr: file_count")?;
+        writeln!(out, "  - id: end_of_header")?;
+        writeln!(out, "    size: 8")?;
+        if !self.is_empty() {
+            writeln!(out, "instances:")?;
+            for (id, info) in self.offset_table.iter().enumerate() {
+                writeln!(out, "  file_{}:", id)?;
+                writeln!(out, "    pos: {}", info.offset())?;
+                writeln!(out, "    size: {}", info.length())?;
+            }
+        };
+        writeln!(out, "types:")?;
+        writeln!(out, "  toc_entry:")?;
+        writeln!(out, "    seq:")?;
+        writeln!(out, "      - id: offset")?;
+        writeln!(out, "        type: u4")?;
+        writeln!(out, "      - id: length")?;
+        writeln!(out, "        type: u4")?;
+        Ok(())
+    }
+
+    fn write_010<W: Write>(&self, out: &mut W) -> Result<(), CPackError> {
+        let endian = match self.variant() {
+            HeaderVariant::LittleEndian => "LittleEndian",
+            HeaderVariant::BigEndian => "BigEndian",
+        };
+        writeln!(out, "// cpack archive layout, generated by pmd_cpack")?;
+        writeln!(out, "{}();", endian)?;
+        writeln!(out, "typedef struct {{")?;
+        writeln!(out, "    uint32 offset;")?;
+        writeln!(out, "    uint32 length;")?;
+        writeln!(out, "}} TocEntry;")?;
+        writeln!(out, "struct {{")?;
+        writeln!(out, "    uchar magic[4];")?;
+        writeln!(out, "    uint32 file_count;")?;
+        if !self.is_empty() {
+            writeln!(out, "    TocEntry table_of_content[{}];", self.len())?;
+        };
+        writeln!(out, "    uchar end_of_header[8];")?;
+        writeln!(out, "}} header;")?;
+        for (id, info) in self.offset_table.iter().enumerate() {
+            // 010 Editor doesn't allow empty arrays
+            if info.length() == 0 {
+                continue;
+            };
+            writeln!(out, "FSeek({});", info.offset())?;
+            writeln!(out, "uchar file_{}[{}];", id, info.length())?;
+        }
+        Ok(())
+    }
+}
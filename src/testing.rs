@@ -0,0 +1,329 @@
+//! Helpers to test code using this crate, available with the ``testing`` feature
+//!
+//! This contain small synthetic archives, built by hand, and functions to build more of them.
+
+use crate::{CPack, CPackWriter, HeaderVariant, ParseOptions, SetLen, Strictness, WriterProfile};
+use std::convert::TryFrom;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+/// The read limit used by [`exercise_read_api`], to keep the allocations small as hostile lengths are expected
+const EXERCISE_READ_LIMIT: u64 = 0x10000;
+
+#[derive(Debug, Clone)]
+/// An in-memory file that can be configured to misbehave, to test error handling against a [`crate::CPack`] or a
+/// [`crate::CPackEditor`] without real files.
+///
+/// # Examples
+/// ```
+/// use pmd_cpack::testing::MockStorage;
+/// use pmd_cpack::CPack;
+///
+/// let storage = MockStorage::new(vec![0; 16]).fail_reads_at(4);
+/// assert!(CPack::new_from_file(storage).is_err());
+/// ```
+pub struct MockStorage {
+    data: Vec<u8>,
+    position: u64,
+    max_read_len: Option<usize>,
+    failing_offset: Option<u64>,
+    remaining_successful_reads: Option<usize>,
+    fail_seeks: bool,
+    failing_write_offset: Option<u64>,
+    fail_set_len: bool,
+    error_kind: io::ErrorKind,
+}
+
+impl MockStorage {
+    /// Create a storage containing ``data``, behaving like an [`io::Cursor`]
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            position: 0,
+            max_read_len: None,
+            failing_offset: None,
+            remaining_successful_reads: None,
+            fail_seeks: false,
+            failing_write_offset: None,
+            fail_set_len: false,
+            error_kind: io::ErrorKind::Other,
+        }
+    }
+
+    /// Never return more than ``max_len`` bytes in a single read (``max_len`` is at least 1)
+    pub fn with_short_reads(mut self, max_len: usize) -> Self {
+        self.max_read_len = Some(max_len.max(1));
+        self
+    }
+
+    /// Fail every read that would include the byte at ``offset``
+    pub fn fail_reads_at(mut self, offset: u64) -> Self {
+        self.failing_offset = Some(offset);
+        self
+    }
+
+    /// Fail every read after the ``count`` first ones
+    pub fn fail_after_reads(mut self, count: usize) -> Self {
+        self.remaining_successful_reads = Some(count);
+        self
+    }
+
+    /// Fail every seek
+    pub fn fail_seeks(mut self) -> Self {
+        self.fail_seeks = true;
+        self
+    }
+
+    /// Fail every write that would include the byte at ``offset``
+    pub fn fail_writes_at(mut self, offset: u64) -> Self {
+        self.failing_write_offset = Some(offset);
+        self
+    }
+
+    /// Fail every change of the length with [`SetLen`]
+    pub fn fail_set_len(mut self) -> Self {
+        self.fail_set_len = true;
+        self
+    }
+
+    /// Use this kind for the injected errors, instead of [`io::ErrorKind::Other`]
+    pub fn with_error_kind(mut self, kind: io::ErrorKind) -> Self {
+        self.error_kind = kind;
+        self
+    }
+
+    /// Return the data of this storage
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn injected_error(&self) -> io::Error {
+        io::Error::new(self.error_kind, "error injected by MockStorage")
+    }
+}
+
+impl Read for MockStorage {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(remaining) = &mut self.remaining_successful_reads {
+            if *remaining == 0 {
+                return Err(self.injected_error());
+            };
+            *remaining -= 1;
+        };
+        let start = self.position.min(self.data.len() as u64) as usize;
+        let mut len = buf.len().min(self.data.len() - start);
+        if let Some(max_read_len) = self.max_read_len {
+            len = len.min(max_read_len);
+        };
+        if let Some(failing_offset) = self.failing_offset {
+            if len > 0 && (start as u64..(start + len) as u64).contains(&failing_offset) {
+                return Err(self.injected_error());
+            };
+        };
+        buf[..len].copy_from_slice(&self.data[start..start + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for MockStorage {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if self.fail_seeks {
+            return Err(self.injected_error());
+        };
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (self.data.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        let new_position = if offset >= 0 {
+            base.checked_add(offset as u64)
+        } else {
+            base.checked_sub(offset.unsigned_abs())
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
+
+impl Write for MockStorage {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(failing_offset) = self.failing_write_offset {
+            if (self.position..self.position + buf.len() as u64).contains(&failing_offset) {
+                return Err(self.injected_error());
+            };
+        };
+        let mut cursor = Cursor::new(&mut self.data);
+        cursor.set_position(self.position);
+        let written = cursor.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SetLen for MockStorage {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        if self.fail_set_len {
+            return Err(self.injected_error());
+        };
+        let len = usize::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "the length doesn't fit in memory"))?;
+        self.data.resize(len, 0);
+        Ok(())
+    }
+}
+
+/// A minimal little endian archive, with no padding, containing the files ``b"hello"`` and ``b"world"``
+pub const HELLO_WORLD: [u8; 42] = [
+    0, 0, 0, 0, // the four zero bytes
+    2, 0, 0, 0, // the number of file
+    32, 0, 0, 0, 5, 0, 0, 0, // the offset and the length of the first file
+    37, 0, 0, 0, 5, 0, 0, 0, // the offset and the length of the second file
+    0, 0, 0, 0, 0, 0, 0, 0, // the end of the header
+    104, 101, 108, 108, 111, // b"hello"
+    119, 111, 114, 108, 100, // b"world"
+];
+
+/// The same archive as [`HELLO_WORLD`], with a big endian header
+pub const HELLO_WORLD_BIG_ENDIAN: [u8; 42] = [
+    0, 0, 0, 0, // the four zero bytes
+    0, 0, 0, 2, // the number of file
+    0, 0, 0, 32, 0, 0, 0, 5, // the offset and the length of the first file
+    0, 0, 0, 37, 0, 0, 0, 5, // the offset and the length of the second file
+    0, 0, 0, 0, 0, 0, 0, 0, // the end of the header
+    104, 101, 108, 108, 111, // b"hello"
+    119, 111, 114, 108, 100, // b"world"
+];
+
+/// An archive containing the file ``b"abc"``, laid out like Explorers of Sky does, with the header and the file padded to 16 bytes with `0xFF`
+pub const SINGLE_ALIGNED: [u8; 48] = [
+    0, 0, 0, 0, // the four zero bytes
+    1, 0, 0, 0, // the number of file
+    32, 0, 0, 0, 3, 0, 0, 0, // the offset and the length of the file
+    0, 0, 0, 0, 0, 0, 0, 0, // the end of the header
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // padding of the header
+    97, 98, 99, // b"abc"
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // padding of the file
+];
+
+/// An archive without any file, made of only the header. Some directories of the games contain such archives.
+pub const EMPTY: [u8; 16] = [
+    0, 0, 0, 0, // the four zero bytes
+    0, 0, 0, 0, // the number of file
+    0, 0, 0, 0, 0, 0, 0, 0, // the end of the header
+];
+
+/// An invalid archive, whose four first bytes aren't zero
+pub const BAD_MAGIC: [u8; 16] = [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+/// An invalid archive, whose only file end after the end of the archive
+pub const TRUNCATED: [u8; 26] = [
+    0, 0, 0, 0, // the four zero bytes
+    1, 0, 0, 0, // the number of file
+    24, 0, 0, 0, 5, 0, 0, 0, // the file is 5 bytes long
+    0, 0, 0, 0, 0, 0, 0, 0, // the end of the header
+    104, 101, // only 2 bytes of the file are present
+];
+
+/// Build an archive containing the given files, using the default [`WriterProfile`]
+pub fn build_archive(files: &[&[u8]]) -> Vec<u8> {
+    build_archive_with_profile(files, WriterProfile::default())
+}
+
+/// Build an archive containing the given files, using the given [`WriterProfile`]. Panic if the files doesn't fit in a cpack archive.
+pub fn build_archive_with_profile(files: &[&[u8]], profile: WriterProfile) -> Vec<u8> {
+    let mut writer = CPackWriter::new(profile);
+    for file in files {
+        writer.push(file.to_vec());
+    }
+    let mut out = Vec::new();
+    writer.write(&mut out).expect("the files should fit in a cpack archive");
+    out
+}
+
+/// Build an archive of ``count`` file, the file with the id ``n`` containing ``n`` bytes of value ``n as u8``
+pub fn build_sequential_archive(count: usize) -> Vec<u8> {
+    let files: Vec<Vec<u8>> = (0..count).map(|n| vec![n as u8; n]).collect();
+    let files: Vec<&[u8]> = files.iter().map(|file| &file[..]).collect();
+    build_archive(&files)
+}
+
+/// Parse the given bytes, as a [`MockStorage`] without any failure. Panic if they aren't a valid archive.
+pub fn open_archive(data: &[u8]) -> CPack<MockStorage> {
+    CPack::new_from_file(MockStorage::new(data.to_vec())).expect("the fixture should be a valid archive")
+}
+
+/// Build the header of an archive declaring ``count`` files, followed by the given table of content, without checking
+/// that they are consistent. This allow to build invalid archives, like ones with a huge number of file or offsets
+/// near [`u32::MAX`]. The header is followed by its terminating eight zero bytes only if ``entries`` has ``count`` files.
+pub fn build_raw_header(variant: HeaderVariant, count: u32, entries: &[(u32, u32)]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(16 + entries.len() * 8);
+    header.extend_from_slice(&[0; 4]);
+    header.extend_from_slice(&variant.u32_to_bytes(count));
+    for (offset, length) in entries {
+        header.extend_from_slice(&variant.u32_to_bytes(*offset));
+        header.extend_from_slice(&variant.u32_to_bytes(*length));
+    }
+    if entries.len() as u64 == count as u64 {
+        header.extend_from_slice(&[0; 8]);
+    };
+    header
+}
+
+/// Parse ``data`` with every [`HeaderVariant`] and [`Strictness`], then call every read function on the archives that
+/// could be parsed, including with invalid ids and ranges. The results are discarded: this only check that nothing
+/// panic, and is shared by the fuzz targets and the regression tests of their findings.
+pub fn exercise_read_api(data: &[u8]) {
+    for variant in [HeaderVariant::LittleEndian, HeaderVariant::BigEndian] {
+        for strictness in [Strictness::Lax, Strictness::Normal, Strictness::Strict] {
+            let options = ParseOptions {
+                variant,
+                strictness,
+                read_limit: Some(EXERCISE_READ_LIMIT),
+                ..Default::default()
+            };
+            if let Ok(pack) = CPack::new_with_options(Cursor::new(data), options) {
+                exercise_archive(&pack);
+            };
+        }
+    }
+}
+
+fn exercise_archive<F: Read + Seek>(pack: &CPack<F>) {
+    let _ = pack.analyze_layout();
+    let _ = pack.padding_report();
+    let _ = pack.infer_padding_byte();
+    let _ = pack.total_payload_len();
+    let _ = pack.unique_payload_len();
+    let _ = pack.total_padding_len();
+    let _ = pack.annotated_dump(&mut io::sink());
+    let _ = pack.search(b"\0\0");
+    let _ = pack.tree_with_depth(2);
+    let _ = pack.find_duplicates();
+    for id in 0..=pack.len() {
+        let _ = pack.entry_info(id);
+        let _ = pack.slack(id);
+        let _ = pack.entry_overlaps(id);
+        let _ = pack.read_file_to_vec(id);
+        let _ = pack.read_entry_range(id, 1, 2);
+        let _ = pack.read_entry_range(id, u64::MAX, 2);
+        if let Ok(chunks) = pack.entry_chunks(id, 0x1000) {
+            chunks.for_each(drop);
+        };
+        if let Ok(mut file) = pack.get_file(id) {
+            let _ = file.read_to_end(&mut Vec::new());
+        };
+    }
+    let _ = pack.read_files(&[0, usize::MAX, 0]);
+}
@@ -0,0 +1,74 @@
+use crate::{CPack, CPackError};
+use io_partition::PartitionMutex;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A reader that wait as needed so that no more than a given number of bytes are read per second on average.
+///
+/// The reads are split so that each one take at most a tenth of second worth of data, which keep the throughput smooth.
+/// Seeking doesn't count toward the limit.
+#[derive(Debug)]
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_second: u64,
+    start: Instant,
+    consumed: u64,
+}
+
+impl<R> ThrottledReader<R> {
+    /// Wrap ``inner``, reading at most ``bytes_per_second`` bytes per second (at least one) from it
+    pub fn new(inner: R, bytes_per_second: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_second: bytes_per_second.max(1),
+            start: Instant::now(),
+            consumed: 0,
+        }
+    }
+
+    /// Return the maximum number of bytes read per second
+    pub fn bytes_per_second(&self) -> u64 {
+        self.bytes_per_second
+    }
+
+    /// Return a reference to the wrapped reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Return the wrapped reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let due = Duration::from_secs_f64(self.consumed as f64 / self.bytes_per_second as f64);
+        let elapsed = self.start.elapsed();
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        };
+        let max_chunk = (self.bytes_per_second / 10).max(1) as usize;
+        let chunk_len = buf.len().min(max_chunk);
+        let read = self.inner.read(&mut buf[..chunk_len])?;
+        self.consumed += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: Seek> Seek for ThrottledReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<F: Read + Seek> CPack<F> {
+    /// Same as [`CPack::get_file`], but the returned reader read at most ``bytes_per_second`` bytes per second.
+    ///
+    /// Each reader has its own limit, so this is useful to share the bandwidth between multiple clients reading from the same archive.
+    pub fn get_file_throttled(&self, id: usize, bytes_per_second: u64) -> Result<ThrottledReader<PartitionMutex<F>>, CPackError> {
+        Ok(ThrottledReader::new(self.get_file(id)?, bytes_per_second))
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{detect_container, CPack, CPackError, ContainerKind, LockStrategy, ParseOptions};
+use std::io::{Cursor, Read, Seek};
+
+/// How deep [`CPack::tree`] look into nested archives
+const DEFAULT_TREE_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A file of an archive, as returned by [`CPack::tree`]
+pub struct TreeNode {
+    /// The id of the file in its archive
+    pub id: usize,
+    /// The length of the file, in bytes
+    pub length: u64,
+    /// The kind of container the file seems to be, according to [`detect_container`]
+    pub kind: ContainerKind,
+    /// If the file is a valid cpack archive, the files it contain. Empty otherwise.
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Return the number of node in this tree, including this one
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(TreeNode::count).sum::<usize>()
+    }
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Return the files of this archive with their kind, looking into the files that are cpack archives themselves, recursively.
+    ///
+    /// Nested archives are read in memory to be parsed, up to 8 level deep. Files that look like a cpack but fail to parse are
+    /// returned without children. Other containers (like FARC) are only identified.
+    pub fn tree(&self) -> Result<Vec<TreeNode>, CPackError> {
+        self.tree_with_depth(DEFAULT_TREE_DEPTH)
+    }
+
+    /// Same as [`CPack::tree`], looking at most ``max_depth`` level into nested archives. With 0, nested archives aren't parsed.
+    pub fn tree_with_depth(&self, max_depth: usize) -> Result<Vec<TreeNode>, CPackError> {
+        (0..self.len()).map(|id| self.tree_node(id, max_depth)).collect()
+    }
+
+    fn tree_node(&self, id: usize, max_depth: usize) -> Result<TreeNode, CPackError> {
+        let info = self.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        let magic = self.read_entry_range(id, 0, info.length().min(8))?;
+        let kind = detect_container(&mut Cursor::new(magic))?;
+        let children = match kind {
+            ContainerKind::CPack(variant) if max_depth > 0 => {
+                let options = ParseOptions {
+                    variant,
+                    read_limit: self.read_limit,
+                    ..Default::default()
+                };
+                match CPack::new_with_options(Cursor::new(self.read_file_to_vec(id)?), options) {
+                    Ok(nested) => nested.tree_with_depth(max_depth - 1)?,
+                    Err(_) => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        };
+        Ok(TreeNode {
+            id,
+            length: info.length(),
+            kind,
+            children,
+        })
+    }
+}
@@ -0,0 +1,54 @@
+use crate::hash::sha256;
+use crate::{CPack, CPackError, LockStrategy, Provenance};
+use std::io::{Read, Seek};
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Verify the files read from now on against the length and SHA-256 recorded in ``provenance``, typically
+    /// read from the sidecar written alongside the archive.
+    ///
+    /// Every function reading the content of a file, like [`CPack::read_file_to_vec`], [`CPack::read_files`] or
+    /// [`CPack::get_file`], then return a [`CPackError::ChecksumMismatch`] for modified files. Those returning a reader
+    /// or only part of a file, like [`CPack::read_entry_range`], first read the whole file to verify it.
+    /// Files missing from ``provenance`` aren't verified.
+    pub fn set_checksums(&mut self, provenance: &Provenance) {
+        self.checksums = provenance
+            .entries
+            .iter()
+            .map(|entry| (entry.id, (entry.length, entry.sha256)))
+            .collect();
+    }
+
+    /// Stop verifying the files that are read
+    pub fn clear_checksums(&mut self) {
+        self.checksums.clear();
+    }
+
+    /// Return an error if ``content`` doesn't match the checksum of the file with the given id
+    pub(crate) fn verify_content(&self, id: usize, content: &[u8]) -> Result<(), CPackError> {
+        match self.checksums.get(&id) {
+            Some((length, hash)) if *length != content.len() as u64 || *hash != sha256(content) => {
+                Err(CPackError::ChecksumMismatch(id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Return an error if ``length`` and ``hash``, computed from the file with the given id, doesn't match its checksum
+    pub(crate) fn verify_hash(&self, id: usize, length: u64, hash: &[u8; 32]) -> Result<(), CPackError> {
+        match self.checksums.get(&id) {
+            Some((expected_length, expected_hash)) if *expected_length != length || expected_hash != hash => {
+                Err(CPackError::ChecksumMismatch(id))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Read the file with the given id by chunk if it has a checksum, returning an error if it doesn't match it
+    pub(crate) fn verify_entry(&self, id: usize) -> Result<(), CPackError> {
+        if self.checksums.contains_key(&id) {
+            // hashing a file verify it
+            self.hash_entry(id)?;
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use crate::dump::{Region, RegionKind};
+use crate::writer::align_up;
+use crate::{Alignment, CPack, CPackError, LockStrategy};
+use std::io::{Read, Seek, Write};
+
+const HEADER_COLOR: &str = "#4a78c2";
+const FILE_COLORS: [&str; 2] = ["#5aa55a", "#7cc47c"];
+const MISALIGNED_COLOR: &str = "#d9534f";
+const PADDING_COLOR: &str = "#c8c8c8";
+const GAP_COLOR: &str = "#f0ad4e";
+
+/// The height of a row of the block map, in pixel
+const ROW_HEIGHT: u32 = 16;
+/// The minimum number of bytes in a row, so small archives aren't stretched over every row
+const MIN_BYTES_PER_ROW: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The settings used by [`CPack::render_svg`] and [`CPack::render_html`]
+pub struct VizOptions {
+    /// The width of the image, in pixel
+    pub width: u32,
+    /// The maximum number of rows the archive is split in
+    pub rows: u32,
+    /// The alignment the archive is expected to follow. Files that don't respect it are highlighted, and padding
+    /// longer than needed to respect it is shown as a gap.
+    pub alignment: Alignment,
+}
+
+impl Default for VizOptions {
+    fn default() -> Self {
+        Self {
+            width: 1024,
+            rows: 32,
+            alignment: Alignment::default(),
+        }
+    }
+}
+
+/// A region of the block map, with its color and description
+struct Block {
+    start: u64,
+    end: u64,
+    color: &'static str,
+    title: String,
+}
+
+impl<F: Read + Seek, L: LockStrategy<F>> CPack<F, L> {
+    /// Split the archive in colored blocks: the header, the files (in red if misaligned), the padding and the gaps
+    fn viz_blocks(&self, options: &VizOptions) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut previous_end = 0;
+        for Region { start, end, kind } in self.layout_regions() {
+            match kind {
+                RegionKind::Header => blocks.push(Block {
+                    start,
+                    end,
+                    color: HEADER_COLOR,
+                    title: format!("header ({} files)", self.len()),
+                }),
+                RegionKind::File(id) => {
+                    let aligned = if start == self.data_start() {
+                        start % options.alignment.header.max(1) as u64 == 0
+                    } else {
+                        start % options.alignment.entry.max(1) as u64 == 0
+                    };
+                    blocks.push(Block {
+                        start,
+                        end,
+                        color: if aligned { FILE_COLORS[id % 2] } else { MISALIGNED_COLOR },
+                        title: format!("file {}{}", id, if aligned { "" } else { ", misaligned" }),
+                    })
+                }
+                RegionKind::Padding => {
+                    let alignment = if previous_end == self.header_len() {
+                        options.alignment.header
+                    } else {
+                        options.alignment.entry
+                    };
+                    let needed_end = align_up(start, alignment).min(end);
+                    if needed_end > start {
+                        blocks.push(Block {
+                            start,
+                            end: needed_end,
+                            color: PADDING_COLOR,
+                            title: "padding".to_string(),
+                        });
+                    };
+                    if end > needed_end {
+                        blocks.push(Block {
+                            start: needed_end,
+                            end,
+                            color: GAP_COLOR,
+                            title: "unused space".to_string(),
+                        });
+                    };
+                }
+            };
+            previous_end = previous_end.max(end);
+        }
+        blocks
+    }
+
+    /// Write an SVG image of the layout of the archive, as rows of blocks read from left to right.
+    ///
+    /// The header is blue, the files green (or red if they aren't aligned as expected), the padding gray,
+    /// and the space not needed to respect the expected alignment orange. Hovering a block show its description.
+    pub fn render_svg<W: Write>(&self, out: &mut W, options: &VizOptions) -> Result<(), CPackError> {
+        let width = options.width.max(1) as u64;
+        let rows = options.rows.max(1) as u64;
+        let bytes_per_row = self.archive_len().div_ceil(rows).max(MIN_BYTES_PER_ROW);
+        let used_rows = self.archive_len().div_ceil(bytes_per_row).max(1);
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}">"#,
+            width,
+            used_rows * ROW_HEIGHT as u64
+        )?;
+        for block in self.viz_blocks(options) {
+            let mut position = block.start;
+            while position < block.end {
+                let row = position / bytes_per_row;
+                let row_end = ((row + 1) * bytes_per_row).min(block.end);
+                let x = (position - row * bytes_per_row) * width / bytes_per_row;
+                let x_end = (row_end - row * bytes_per_row) * width / bytes_per_row;
+                writeln!(
+                    out,
+                    r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"><title>{}: 0x{:x}-0x{:x} ({} bytes)</title></rect>"#,
+                    x,
+                    row * ROW_HEIGHT as u64,
+                    (x_end - x).max(1),
+                    ROW_HEIGHT,
+                    block.color,
+                    block.title,
+                    block.start,
+                    block.end,
+                    block.end - block.start
+                )?;
+                position = row_end;
+            }
+        }
+        writeln!(out, "</svg>")?;
+        Ok(())
+    }
+
+    /// Write an HTML page containing the image of [`CPack::render_svg`], a legend and the amount of space used by padding
+    pub fn render_html<W: Write>(&self, out: &mut W, options: &VizOptions) -> Result<(), CPackError> {
+        let blocks = self.viz_blocks(options);
+        let total = |color: &str| -> u64 {
+            blocks
+                .iter()
+                .filter(|block| block.color == color)
+                .map(|block| block.end - block.start)
+                .sum()
+        };
+        let misaligned = blocks.iter().filter(|block| block.color == MISALIGNED_COLOR).count();
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head><meta charset=\"utf-8\"><title>cpack layout</title></head>")?;
+        writeln!(out, "<body>")?;
+        writeln!(
+            out,
+            "<p>{} files, {} bytes. Padding: {} bytes. Unused space: {} bytes. Misaligned files: {}.</p>",
+            self.len(),
+            self.archive_len(),
+            total(PADDING_COLOR),
+            total(GAP_COLOR),
+            misaligned
+        )?;
+        writeln!(out, "<ul>")?;
+        for (color, label) in [
+            (HEADER_COLOR, "header"),
+            (FILE_COLORS[0], "file"),
+            (MISALIGNED_COLOR, "misaligned file"),
+            (PADDING_COLOR, "padding"),
+            (GAP_COLOR, "unused space"),
+        ] {
+            writeln!(out, "<li><span style=\"color: {}\">&#9632;</span> {}</li>", color, label)?;
+        }
+        writeln!(out, "</ul>")?;
+        self.render_svg(out, options)?;
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A non-fatal oddity found while parsing a cpack file
+#[non_exhaustive]
+pub enum ParseWarning {
+    /// The four first bytes aren't zero (only with [`crate::Strictness::Lax`], it's an error otherwise)
+    FourFirstByteNotZero([u8; 4]),
+    /// The file with this id end after the end of the archive (only with [`crate::Strictness::Lax`], it's an error otherwise)
+    EndOfFileOutOfScope(u32),
+    /// The table of content isn't terminated by 8 zero bytes (only with [`crate::Strictness::Lax`], it's an error otherwise)
+    EndOfHeaderNotZero([u8; 8]),
+    /// The file with this id start inside the header (an error with [`crate::Strictness::Strict`])
+    EntryOverlapHeader(u32),
+    /// The padding byte at this offset differ from the previous padding bytes. The padding is only read while parsing
+    /// with [`crate::Strictness::Strict`], where this is an error, or by [`crate::CPack::check_padding`].
+    /// Contain the offset, the previous padding byte and the byte found.
+    InconsistentPadding(u64, u8, u8),
+    /// There are more bytes after the last file (and its alignment padding) than expected. Contain the offset and length of those bytes.
+    TrailingData(u64, u64),
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FourFirstByteNotZero(value) => write!(f, "the four first bytes of the file should be zero, but they are {:?}", value),
+            Self::EndOfFileOutOfScope(file_id) => write!(f, "the file (id: {}) end after the end of the archive", file_id),
+            Self::EndOfHeaderNotZero(value) => write!(f, "the end of the header should be 8 zero bytes, but found {:?}", value),
+            Self::EntryOverlapHeader(file_id) => write!(f, "the file (id: {}) start inside the header", file_id),
+            Self::InconsistentPadding(offset, expected, found) => write!(f, "the padding byte at {} is {}, but previous padding bytes are {}", offset, found, expected),
+            Self::TrailingData(offset, len) => write!(f, "there are {} unexpected bytes at the end of the archive, starting at {}", len, offset),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{CPack, CPackError};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Convert a watcher error, keeping the underlying IO error if there is one
+fn to_cpack_error(err: notify::Error) -> CPackError {
+    match err.kind {
+        notify::ErrorKind::Io(io_err) => CPackError::IOError(io_err),
+        _ => CPackError::IOError(io::Error::other(err)),
+    }
+}
+
+/// Watch an archive file, calling a function each time it is modified, until it is dropped.
+///
+/// The directory containing the archive is watched rather than the file itself, so replacing the archive with a rename
+/// (like [`crate::CPackEditor::save_atomic`] does) is also reported. The function is called from a background thread.
+pub struct CPackWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl CPackWatcher {
+    /// Start watching the archive at ``path``, calling ``on_change`` after each modification of it.
+    ///
+    /// A single modification may result in multiple calls, as most tools write a file in multiple steps.
+    pub fn new<P: AsRef<Path>, C: FnMut() + Send + 'static>(path: P, mut on_change: C) -> Result<Self, CPackError> {
+        let path = path.as_ref().canonicalize()?;
+        let directory = path.parent().unwrap_or(&path).to_path_buf();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(_) => return,
+            };
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                && event.paths.iter().any(|changed| changed == &path)
+            {
+                on_change();
+            };
+        })
+        .map_err(to_cpack_error)?;
+        watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(to_cpack_error)?;
+        Ok(Self { _watcher: watcher })
+    }
+
+    /// Start watching the archive at ``path``, calling [`CPack::reload`] on ``pack`` after each modification of it.
+    ///
+    /// ``pack`` should read from ``path`` without caching its content. As the archive may be read while partially written,
+    /// a failed reload is ignored, leaving ``pack`` unchanged until the next modification. If the archive is replaced by another file
+    /// rather than modified in place, ``pack`` keep reading the old file, so use [`CPackWatcher::new`] to open it again instead.
+    pub fn reload_on_change<P, F>(path: P, pack: Arc<Mutex<CPack<F>>>) -> Result<Self, CPackError>
+    where
+        P: AsRef<Path>,
+        F: Read + Seek + Send + 'static,
+    {
+        Self::new(path, move || {
+            if let Ok(mut pack) = pack.lock() {
+                let _ = pack.reload();
+            };
+        })
+    }
+}
@@ -0,0 +1,697 @@
+use crate::extract::extracted_name;
+use crate::hash::{sha256, HashingWriter};
+use crate::{
+    CPack, CPackError, EntryProcessor, HeaderVariant, ProcessorChain, Provenance, ProvenanceEntry, SaveOptions,
+};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, IoSlice, Read, Seek, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The alignment convention of a cpack file
+pub struct Alignment {
+    /// The boundary the end of the header (and so the first file) is padded to
+    pub header: u32,
+    /// The boundary every file is padded to
+    pub entry: u32,
+}
+
+impl Default for Alignment {
+    /// Pad the header and the files to 16 bytes, the alignment of the files of Explorers of Sky. See
+    /// [`crate::GamePreset`] for its header alignment.
+    fn default() -> Self {
+        Self {
+            header: 16,
+            entry: 16,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The settings used by [`CPackWriter`] to lay out the archive
+pub struct WriterProfile {
+    /// The alignment of the header and of the files
+    pub alignment: Alignment,
+    /// The byte used to fill the padding
+    pub padding_byte: u8,
+    /// The header variant to write
+    pub variant: HeaderVariant,
+}
+
+impl Default for WriterProfile {
+    fn default() -> Self {
+        Self::from(Alignment::default())
+    }
+}
+
+impl From<Alignment> for WriterProfile {
+    /// Create a little endian profile with the given alignment, padding with `0xFF`
+    fn from(alignment: Alignment) -> Self {
+        Self {
+            alignment,
+            padding_byte: 0xFF,
+            variant: HeaderVariant::default(),
+        }
+    }
+}
+
+/// Round `value` up to the next multiple of `alignment`. An alignment of 0 is treated as 1.
+pub(crate) fn align_up(value: u64, alignment: u32) -> u64 {
+    let alignment = alignment.max(1) as u64;
+    match value % alignment {
+        0 => value,
+        rem => value + (alignment - rem),
+    }
+}
+
+/// Same as [`align_up`], but return [`CPackError::OffsetOverflow`] if the result doesn't fit in 64 bits
+fn checked_align_up(value: u64, alignment: u32) -> Result<u64, CPackError> {
+    let alignment = alignment.max(1) as u64;
+    match value % alignment {
+        0 => Ok(value),
+        rem => value.checked_add(alignment - rem).ok_or(CPackError::OffsetOverflow(value)),
+    }
+}
+
+fn to_u32(value: u64) -> Result<u32, CPackError> {
+    u32::try_from(value).map_err(|_| CPackError::OffsetOverflow(value))
+}
+
+/// Where the content of a file added to a [`CPackWriter`] come from
+pub enum EntrySource<'a> {
+    /// Data in memory, either owned or borrowed
+    Bytes(Cow<'a, [u8]>),
+    /// A file on disk, read when the archive is written.
+    ///
+    /// It is copied with [`io::copy`], so when writing to a [`File`] (or a [`io::BufWriter`] over one, like
+    /// [`CPackWriter::save_atomic`] does), the copy is done by the kernel with ``copy_file_range`` or ``sendfile`` where available.
+    Path(PathBuf),
+    /// A reader, from which the given number of bytes will be read when the archive is written
+    Reader(Box<dyn Read + 'a>, u64),
+    /// A memory mapped file, written directly from the mapping without being copied in a buffer first
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl<'a> EntrySource<'a> {
+    /// The content of the file ``id`` of ``pack``, to be copied as-is without being loaded in memory
+    pub fn raw_from<F: Read + Seek + 'a>(pack: &CPack<F>, id: usize) -> Result<Self, CPackError> {
+        let info = pack.entry_info(id).ok_or(CPackError::EntryNotFound(id))?;
+        Ok(Self::Reader(Box::new(pack.get_file(id)?), info.length()))
+    }
+}
+
+impl<'a> From<Vec<u8>> for EntrySource<'a> {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(Cow::Owned(bytes))
+    }
+}
+
+impl<'a> From<&'a [u8]> for EntrySource<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self::Bytes(Cow::Borrowed(bytes))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<'a> From<memmap2::Mmap> for EntrySource<'a> {
+    fn from(map: memmap2::Mmap) -> Self {
+        Self::Mapped(map)
+    }
+}
+
+impl<'a> From<PathBuf> for EntrySource<'a> {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl<'a> From<&Path> for EntrySource<'a> {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl<'a> fmt::Debug for EntrySource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Self::Path(path) => write!(f, "Path({:?})", path),
+            Self::Reader(_, len) => write!(f, "Reader({} bytes)", len),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => write!(f, "Mapped({} bytes)", map.len()),
+        }
+    }
+}
+
+/// The content of a file that will be written, with its length known
+enum Entry<'a> {
+    Bytes(Cow<'a, [u8]>),
+    Path(PathBuf, u64),
+    Reader(Box<dyn Read + 'a>, u64),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    /// Space reserved with [`CPackWriter::reserve`], not filled yet
+    Reserved(u64),
+}
+
+impl<'a> Entry<'a> {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Path(_, len) | Self::Reader(_, len) | Self::Reserved(len) => *len,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map.len() as u64,
+        }
+    }
+
+    /// Write the content of the file followed by ``padding``
+    fn write_to<W: Write>(self, out: &mut W, padding: &[u8]) -> Result<(), CPackError> {
+        match self {
+            Self::Bytes(bytes) => write_bytes(out, &bytes, padding)?,
+            Self::Path(path, len) => {
+                copy_exact(&mut File::open(path)?, out, len)?;
+                out.write_all(padding)?;
+            }
+            Self::Reader(mut reader, len) => {
+                copy_exact(&mut reader, out, len)?;
+                out.write_all(padding)?;
+            }
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => write_bytes(out, &map, padding)?,
+            // checked before writing anything, this is never reached
+            Self::Reserved(_) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "a reserved file wasn't filled").into())
+            }
+        };
+        Ok(())
+    }
+
+    /// Read the whole content of the file in memory
+    fn into_bytes(self) -> Result<Vec<u8>, CPackError> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes.into_owned()),
+            // the length given with a reader isn't trusted to preallocate the content: only up to ``len`` bytes are
+            // read, and the copied length is checked by ``write_to``
+            other => {
+                let mut bytes = Vec::new();
+                other.write_to(&mut bytes, &[])?;
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+/// Where a file is taken from while writing
+enum Slot<'a> {
+    /// The file with this index in the list of in-memory files
+    Memory(usize),
+    /// A file that is read while being written
+    Streamed(Entry<'a>),
+}
+
+/// Write ``bytes`` followed by ``padding``
+fn write_bytes<W: Write>(out: &mut W, bytes: &[u8], padding: &[u8]) -> io::Result<()> {
+    write_all_vectored(out, &mut [IoSlice::new(bytes), IoSlice::new(padding)])
+}
+
+/// Copy exactly ``len`` bytes from ``reader`` to ``out``
+fn copy_exact<R: Read, W: Write>(reader: &mut R, out: &mut W, len: u64) -> Result<(), CPackError> {
+    let copied = io::copy(&mut reader.take(len), out)?;
+    if copied != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "the source of a file ended before its expected length",
+        )
+        .into());
+    };
+    Ok(())
+}
+
+/// Write every slice, with as few call to [`Write::write_vectored`] as possible
+fn write_all_vectored<W: Write>(out: &mut W, mut slices: &mut [IoSlice<'_>]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut slices, 0);
+    while !slices.is_empty() {
+        match out.write_vectored(slices) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// The maximum size of the padding written in the same vectored write as the file it follow
+const MAX_INLINE_PADDING: usize = 0x1000;
+
+impl<'a> fmt::Debug for Entry<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Self::Path(path, len) => write!(f, "Path({:?}, {} bytes)", path, len),
+            Self::Reader(_, len) => write!(f, "Reader({} bytes)", len),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => write!(f, "Mapped({} bytes)", map.len()),
+            Self::Reserved(len) => write!(f, "Reserved({} bytes)", len),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A file whose space was reserved with [`CPackWriter::reserve`], to be filled later with [`CPackWriter::fill`]
+pub struct EntryHandle(usize);
+
+impl EntryHandle {
+    /// Return the id the file will have in the archive
+    pub fn id(self) -> usize {
+        self.0
+    }
+}
+
+/// The position of everything in a cpack file about to be written
+pub(crate) struct Layout {
+    /// The header, including the table of content and its terminator, but not its padding
+    pub(crate) header: Vec<u8>,
+    /// The number of padding bytes following the header
+    pub(crate) header_padding: u64,
+    /// The number of padding bytes following each file
+    pub(crate) paddings: Vec<u64>,
+}
+
+impl Layout {
+    /// Compute the layout of an archive containing files of the given lengths
+    pub(crate) fn compute(profile: &WriterProfile, lengths: &[u64]) -> Result<Self, CPackError> {
+        Self::compute_with_alignments(profile, lengths, &[])
+    }
+
+    /// Compute the layout of an archive containing files of the given lengths, the start of the files with an id in
+    /// ``alignments`` being also aligned to the given boundary, by extending the padding preceding them
+    pub(crate) fn compute_with_alignments(
+        profile: &WriterProfile,
+        lengths: &[u64],
+        alignments: &[Option<u32>],
+    ) -> Result<Self, CPackError> {
+        let alignment = profile.alignment;
+        let variant = profile.variant;
+        let header_len = 8 + lengths.len() as u64 * 8 + 8;
+        let mut header_padding = checked_align_up(header_len, alignment.header)? - header_len;
+
+        let mut header = Vec::with_capacity(header_len as usize);
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&variant.u32_to_bytes(to_u32(lengths.len() as u64)?));
+        let mut paddings: Vec<u64> = Vec::with_capacity(lengths.len());
+        let mut position = header_len + header_padding;
+        for (id, len) in lengths.iter().enumerate() {
+            if let Some(entry_alignment) = alignments.get(id).copied().flatten() {
+                let start = checked_align_up(position, entry_alignment)?;
+                match paddings.last_mut() {
+                    Some(padding) => *padding += start - position,
+                    None => header_padding += start - position,
+                };
+                position = start;
+            };
+            let end = position.checked_add(*len).ok_or(CPackError::OffsetOverflow(u64::MAX))?;
+            to_u32(end)?;
+            header.extend_from_slice(&variant.u32_to_bytes(position as u32));
+            header.extend_from_slice(&variant.u32_to_bytes(*len as u32));
+            position = checked_align_up(end, alignment.entry)?;
+            paddings.push(position - end);
+        }
+        header.extend_from_slice(&[0; 8]);
+
+        Ok(Self {
+            header,
+            header_padding,
+            paddings,
+        })
+    }
+}
+
+#[derive(Debug)]
+/// A file that will be written, with information about where it came from
+struct PendingFile<'a> {
+    content: Entry<'a>,
+    source: Option<String>,
+    /// The boundary the start of the file is aligned to, in addition to the alignment of the profile
+    alignment: Option<u32>,
+    /// The length reserved with [`CPackWriter::reserve`], if the file was added this way
+    reserved: Option<u64>,
+    /// The processors applied to this file only, before the ones of the writer
+    processors: ProcessorChain,
+}
+
+impl<'a> PendingFile<'a> {
+    /// Query the length of ``source``, recording the path of files on disk as their source
+    fn from_source(source: EntrySource<'a>) -> Result<Self, CPackError> {
+        Ok(match source {
+            EntrySource::Bytes(bytes) => Entry::Bytes(bytes).into(),
+            EntrySource::Path(path) => {
+                let len = path.metadata()?.len();
+                PendingFile {
+                    source: Some(path.display().to_string()),
+                    content: Entry::Path(path, len),
+                    alignment: None,
+                    reserved: None,
+                    processors: ProcessorChain::new(),
+                }
+            }
+            EntrySource::Reader(reader, len) => Entry::Reader(reader, len).into(),
+            #[cfg(feature = "mmap")]
+            EntrySource::Mapped(map) => Entry::Mapped(map).into(),
+        })
+    }
+
+    /// Run the content of the file through its processors then ``writer_processors``, loading it in memory.
+    /// If they rename the file, the new name is recorded as its source.
+    fn process(&mut self, id: usize, writer_processors: &ProcessorChain) -> Result<(), CPackError> {
+        if self.processors.is_empty() && writer_processors.is_empty() {
+            return Ok(());
+        };
+        let mut content = mem::replace(&mut self.content, Entry::Reserved(0)).into_bytes()?;
+        let original_name = self.source.clone().unwrap_or_else(|| extracted_name(id));
+        let mut name = original_name.clone();
+        self.processors.process(id, &mut name, &mut content)?;
+        writer_processors.process(id, &mut name, &mut content)?;
+        if name != original_name {
+            self.source = Some(name);
+        };
+        self.content = Entry::Bytes(Cow::Owned(content));
+        Ok(())
+    }
+}
+
+impl<'a> From<Entry<'a>> for PendingFile<'a> {
+    fn from(content: Entry<'a>) -> Self {
+        let reserved = match content {
+            Entry::Reserved(len) => Some(len),
+            _ => None,
+        };
+        Self {
+            content,
+            source: None,
+            alignment: None,
+            reserved,
+            processors: ProcessorChain::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// A structure that allow to create a cpack file
+///
+/// Files are identified by the order they are pushed in.
+pub struct CPackWriter<'a> {
+    files: Vec<PendingFile<'a>>,
+    profile: WriterProfile,
+    processors: ProcessorChain,
+}
+
+impl<'a> CPackWriter<'a> {
+    /// Create an empty [`CPackWriter`] that will use the given profile
+    pub fn new(profile: WriterProfile) -> Self {
+        Self {
+            files: Vec::new(),
+            profile,
+            processors: ProcessorChain::new(),
+        }
+    }
+
+    /// Return the profile used by this writer
+    pub fn profile(&self) -> &WriterProfile {
+        &self.profile
+    }
+
+    /// add a file to the cpack
+    pub fn push(&mut self, file: Vec<u8>) {
+        self.files.push(Entry::Bytes(Cow::Owned(file)).into());
+    }
+
+    /// add a file to the cpack, whose content come from ``source``.
+    ///
+    /// The length of files on disk is queried immediately, but their content is only read when writing.
+    /// Their path is recorded as their source for [`CPackWriter::write_with_provenance`].
+    pub fn add<S: Into<EntrySource<'a>>>(&mut self, source: S) -> Result<(), CPackError> {
+        self.files.push(PendingFile::from_source(source.into())?);
+        Ok(())
+    }
+
+    /// add a file to the cpack, recording where it came from (typically the path of the loose file) for [`CPackWriter::write_with_provenance`]
+    pub fn push_with_source<S: Into<String>>(&mut self, file: Vec<u8>, source: S) {
+        self.files.push(PendingFile {
+            content: Entry::Bytes(Cow::Owned(file)),
+            source: Some(source.into()),
+            alignment: None,
+            reserved: None,
+            processors: ProcessorChain::new(),
+        });
+    }
+
+    /// add a file to the cpack like [`CPackWriter::add`], whose start will also be aligned to ``alignment`` bytes.
+    ///
+    /// This is for files that need a stricter alignment than the other ones of the archive, like those the game load
+    /// with DMA. The padding preceding the file is extended as needed, filled with the padding byte of the profile.
+    pub fn add_with_alignment<S: Into<EntrySource<'a>>>(&mut self, source: S, alignment: u32) -> Result<(), CPackError> {
+        self.add(source)?;
+        if let Some(file) = self.files.last_mut() {
+            file.alignment = Some(alignment);
+        };
+        Ok(())
+    }
+
+    /// add a file to the cpack like [`CPackWriter::add`], that will be run through ``processors`` when the archive is
+    /// written, before the processors of the writer (see [`CPackWriter::set_processors`])
+    pub fn add_with_processors<S: Into<EntrySource<'a>>>(
+        &mut self,
+        source: S,
+        processors: ProcessorChain,
+    ) -> Result<(), CPackError> {
+        self.add(source)?;
+        if let Some(file) = self.files.last_mut() {
+            file.processors = processors;
+        };
+        Ok(())
+    }
+
+    /// Set the processors every file is run through when the archive is written, like to compress or encrypt them,
+    /// replacing the previous ones. This avoid writing the processed files to a temporary directory first.
+    ///
+    /// The ``name`` given to the processors is the source of the file (like its path) if known, ``<id>.bin`` otherwise.
+    /// A renamed file is recorded with its new name as its source in the provenance. As the length of the processed
+    /// files need to be known to write the header, every processed file is loaded in memory before writing anything.
+    pub fn set_processors(&mut self, processors: ProcessorChain) {
+        self.processors = processors;
+    }
+
+    /// Reserve the place of a file of ``len`` bytes, whose content will be given later with [`CPackWriter::fill`].
+    ///
+    /// This allow to add the files in their final order when the content of some of them is generated later, like
+    /// a table of the offsets of the other files. Writing the archive fail with [`CPackError::UnfilledEntry`] if the
+    /// file wasn't filled by then.
+    pub fn reserve(&mut self, len: u64) -> EntryHandle {
+        self.files.push(Entry::Reserved(len).into());
+        EntryHandle(self.files.len() - 1)
+    }
+
+    /// Give the content of a file reserved with [`CPackWriter::reserve`]. It must have the reserved length, otherwise
+    /// [`CPackError::ReservedLengthMismatch`] is returned. A file may be filled again, replacing its content.
+    ///
+    /// [`CPackError::EntryNotFound`] is returned if the handle doesn't come from this writer.
+    pub fn fill<S: Into<EntrySource<'a>>>(&mut self, handle: EntryHandle, source: S) -> Result<(), CPackError> {
+        let id = handle.id();
+        let slot = self.files.get_mut(id).ok_or(CPackError::EntryNotFound(id))?;
+        let reserved_len = slot.reserved.ok_or(CPackError::EntryNotFound(id))?;
+        let mut file = PendingFile::from_source(source.into())?;
+        if file.content.len() != reserved_len {
+            return Err(CPackError::ReservedLengthMismatch(id, reserved_len, file.content.len()));
+        };
+        file.reserved = slot.reserved;
+        file.alignment = slot.alignment;
+        file.processors = mem::take(&mut slot.processors);
+        *slot = file;
+        Ok(())
+    }
+
+    /// add a file to the cpack, copying its content as-is from the file ``id`` of ``source`` when writing.
+    ///
+    /// The data isn't loaded in memory, so this is cheap even for big files.
+    pub fn add_raw_from<F: Read + Seek + 'a>(
+        &mut self,
+        source: &CPack<F>,
+        id: usize,
+    ) -> Result<(), CPackError> {
+        self.add(EntrySource::raw_from(source, id)?)
+    }
+
+    /// Return the number of file that will be written
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Return true if no file were added
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Write the cpack file to ``out``
+    pub fn write<W: Write>(self, out: &mut W) -> Result<(), CPackError> {
+        self.write_inner(out, None)
+    }
+
+    /// Write the cpack file to ``out``, and return the [`Provenance`] of every written file, to be stored in a sidecar file.
+    pub fn write_with_provenance<W: Write>(self, out: &mut W) -> Result<Provenance, CPackError> {
+        let mut provenance = Provenance::new();
+        self.write_inner(out, Some(&mut provenance.entries))?;
+        Ok(provenance)
+    }
+
+    fn write_inner<W: Write>(
+        mut self,
+        out: &mut W,
+        provenance: Option<&mut Vec<ProvenanceEntry>>,
+    ) -> Result<(), CPackError> {
+        if let Some(id) = self.files.iter().position(|file| matches!(file.content, Entry::Reserved(_))) {
+            return Err(CPackError::UnfilledEntry(id));
+        };
+        for (id, file) in self.files.iter_mut().enumerate() {
+            file.process(id, &self.processors)?;
+        }
+        let lengths: Vec<u64> = self.files.iter().map(|file| file.content.len()).collect();
+        let alignments: Vec<Option<u32>> = self.files.iter().map(|file| file.alignment).collect();
+        let layout = Layout::compute_with_alignments(&self.profile, &lengths, &alignments)?;
+        let padding_byte = self.profile.padding_byte;
+        out.write_all(&layout.header)?;
+        write_padding(out, padding_byte, layout.header_padding)?;
+
+        let inline_padding = vec![
+            padding_byte;
+            (self.profile.alignment.entry.max(1) as usize - 1).min(MAX_INLINE_PADDING)
+        ];
+
+        // The in-memory files can be hashed by worker threads while this one write everything in order.
+        // The other ones are hashed while they are streamed to the output.
+        let mut memory = Vec::new();
+        let mut slots = Vec::with_capacity(self.files.len());
+        let mut sources = Vec::with_capacity(self.files.len());
+        for (id, file) in self.files.into_iter().enumerate() {
+            sources.push(file.source);
+            match file.content {
+                Entry::Bytes(bytes) => {
+                    slots.push(Slot::Memory(memory.len()));
+                    memory.push((id, bytes));
+                }
+                other => slots.push(Slot::Streamed(other)),
+            }
+        }
+        let mut hashes = vec![[0; 32]; slots.len()];
+
+        thread::scope(|scope| -> Result<(), CPackError> {
+            let workers: Vec<_> = if provenance.is_some() {
+                let nb_workers = thread::available_parallelism()
+                    .map(|nb| nb.get())
+                    .unwrap_or(1)
+                    .min(memory.len());
+                (0..nb_workers)
+                    .map(|worker| {
+                        let memory = &memory;
+                        scope.spawn(move || {
+                            memory
+                                .iter()
+                                .skip(worker)
+                                .step_by(nb_workers)
+                                .map(|(id, bytes)| (*id, sha256(bytes)))
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for ((id, slot), padding_len) in slots.into_iter().enumerate().zip(layout.paddings) {
+                let padding_len = padding_len as usize;
+                let padding: &[u8] = inline_padding.get(..padding_len).unwrap_or(&[]);
+                match slot {
+                    Slot::Memory(index) => write_bytes(out, &memory[index].1, padding)?,
+                    Slot::Streamed(content) if provenance.is_some() => {
+                        let mut hashing_out = HashingWriter::new(out);
+                        content.write_to(&mut hashing_out, &[])?;
+                        hashes[id] = hashing_out.finalize();
+                        out.write_all(padding)?;
+                    }
+                    Slot::Streamed(content) => content.write_to(out, padding)?,
+                };
+                write_padding(out, padding_byte, (padding_len - padding.len()) as u64)?;
+            }
+
+            for worker in workers {
+                let worker_hashes = worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (id, hash) in worker_hashes {
+                    hashes[id] = hash;
+                }
+            }
+            Ok(())
+        })?;
+
+        if let Some(provenance) = provenance {
+            for (id, ((length, sha256), source)) in lengths
+                .into_iter()
+                .zip(hashes)
+                .zip(sources)
+                .enumerate()
+            {
+                provenance.push(ProvenanceEntry {
+                    id,
+                    length,
+                    sha256,
+                    source,
+                });
+            }
+        };
+        Ok(())
+    }
+
+    /// Write the cpack file to ``path``, through a temporary file in the same directory that is then renamed.
+    ///
+    /// Either the whole file is written, or ``path`` is left untouched, even if the program crash mid-write.
+    pub fn save_atomic<P: AsRef<Path>>(self, path: P) -> Result<(), CPackError> {
+        self.save_atomic_with_options(path, &SaveOptions::default())
+    }
+
+    /// Same as [`CPackWriter::save_atomic`], using the given [`SaveOptions`]
+    pub fn save_atomic_with_options<P: AsRef<Path>>(
+        self,
+        path: P,
+        options: &SaveOptions,
+    ) -> Result<(), CPackError> {
+        crate::save::save_atomic(path, options, |file| self.write(file))
+    }
+}
+
+/// The maximum number of padding bytes written with a single call to [`Write::write_all`]
+pub(crate) const PADDING_CHUNK_LEN: u64 = 0x10000;
+
+fn write_padding<W: Write>(out: &mut W, padding_byte: u8, mut len: u64) -> Result<(), CPackError> {
+    if len == 0 {
+        return Ok(());
+    };
+    let chunk = vec![padding_byte; len.min(PADDING_CHUNK_LEN) as usize];
+    while len > 0 {
+        let chunk_len = len.min(chunk.len() as u64);
+        out.write_all(&chunk[..chunk_len as usize])?;
+        len -= chunk_len;
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Operations over every archive of a directory with [`pmd_cpack::batch`]
+
+use pmd_cpack::batch::open_dir;
+use pmd_cpack::testing::build_archive;
+use pmd_cpack::{HeaderVariant, Strictness};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+/// Return a new empty directory for the test ``name``
+fn test_dir(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-batch-{}-{}", process::id(), name));
+    fs::remove_dir_all(&directory).ok();
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Return a directory containing two archives, a file that isn't one and a truncated archive
+fn rom_dir(name: &str) -> PathBuf {
+    let root = test_dir(name);
+    fs::create_dir_all(root.join("data/nested")).unwrap();
+    fs::write(root.join("data/font.bin"), build_archive(&[b"first", b"second"])).unwrap();
+    fs::write(root.join("data/nested/effect.bin"), build_archive(&[b"third"])).unwrap();
+    fs::write(root.join("data/readme.txt"), b"not an archive").unwrap();
+    let mut truncated = build_archive(&[b"fourth"]);
+    truncated.truncate(20);
+    fs::write(root.join("broken.bin"), truncated).unwrap();
+    root
+}
+
+#[test]
+fn archives_are_found_by_content() {
+    let root = rom_dir("found");
+    let batch = open_dir(&root).unwrap();
+    let paths: Vec<_> = batch.archives().iter().map(|archive| archive.path.clone()).collect();
+    assert_eq!(paths, [root.join("data/font.bin"), root.join("data/nested/effect.bin")]);
+    assert!(batch.archives().iter().all(|archive| archive.variant == HeaderVariant::LittleEndian));
+    assert_eq!(batch.failures().len(), 1);
+    assert_eq!(batch.failures()[0].0, root.join("broken.bin"));
+    assert_eq!(batch.archives()[0].open().unwrap().read_file_to_vec(1).unwrap(), b"second");
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn operations_run_on_every_archive() {
+    let root = rom_dir("operations");
+    let out = test_dir("operations-out");
+    let batch = open_dir(&root).unwrap();
+
+    for report in batch.validate(Strictness::Strict) {
+        assert!(report.unwrap().warnings.is_empty());
+    }
+    let stats: Vec<_> = batch.stats().into_iter().map(Result::unwrap).collect();
+    assert_eq!(stats[0].entry_count, 2);
+    assert_eq!(stats[1].payload_len, 5);
+    for result in batch.extract_all(&out) {
+        result.unwrap();
+    }
+    assert_eq!(fs::read(out.join("data/font.bin/1.bin")).unwrap(), b"second");
+    assert_eq!(fs::read(out.join("data/nested/effect.bin/0.bin")).unwrap(), b"third");
+
+    // the archives are opened again by each operation, so they see the modifications made meanwhile
+    fs::write(root.join("data/font.bin"), b"\0\0\0\0 no longer an archive").unwrap();
+    assert!(batch.stats()[0].is_err());
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&out).unwrap();
+}
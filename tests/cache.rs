@@ -0,0 +1,52 @@
+//! Storing processed files in an ``ExtractionCache``
+
+use pmd_cpack::{CPack, CPackWriter, ExtractionCache, WriterProfile};
+use std::fs;
+use std::io::Cursor;
+use std::thread;
+
+fn open_cache(name: &str) -> ExtractionCache {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-cache-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&directory);
+    ExtractionCache::open(directory).unwrap()
+}
+
+fn pack() -> CPack<Cursor<Vec<u8>>> {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    writer.push(b"content".to_vec());
+    let mut out = Vec::new();
+    writer.write(&mut out).unwrap();
+    CPack::new_from_file(Cursor::new(out)).unwrap()
+}
+
+#[test]
+fn concurrent_inserts_all_succeed() {
+    let cache = open_cache("concurrent");
+    let pack = pack();
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                let processed = cache.get_or_insert_with(&pack, 0, |raw| Ok(raw.repeat(2))).unwrap();
+                assert_eq!(processed, b"contentcontent");
+            });
+        }
+    });
+    // only the entry is left, without temporary file
+    assert_eq!(fs::read_dir(cache.directory()).unwrap().count(), 1);
+    let cached = cache.get_or_insert_with(&pack, 0, |_| panic!("the entry should be cached")).unwrap();
+    assert_eq!(cached, b"contentcontent");
+    fs::remove_dir_all(cache.directory()).unwrap();
+}
+
+#[test]
+fn clear_only_remove_entries() {
+    let cache = open_cache("clear");
+    let pack = pack();
+    cache.get_or_insert_with(&pack, 0, Ok).unwrap();
+    let temporary = cache.directory().join(".0123.42.0.tmp");
+    fs::write(&temporary, b"being written").unwrap();
+    cache.clear().unwrap();
+    assert_eq!(fs::read_dir(cache.directory()).unwrap().count(), 1);
+    assert!(temporary.exists());
+    fs::remove_dir_all(cache.directory()).unwrap();
+}
@@ -0,0 +1,51 @@
+//! Verifying the files read from an archive against the checksums of its provenance
+
+use pmd_cpack::{CPack, CPackError, CPackWriter, WriterProfile};
+use std::io::{Cursor, Read};
+
+/// Return an archive of two files whose second one was modified after its provenance was recorded
+fn modified_archive() -> CPack<Cursor<Vec<u8>>> {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    writer.push(b"first file".to_vec());
+    writer.push(b"second file".to_vec());
+    let mut data = Vec::new();
+    let provenance = writer.write_with_provenance(&mut data).unwrap();
+    let mut pack = CPack::new_from_file(Cursor::new(data)).unwrap();
+    let offset = pack.entry_info(1).unwrap().offset() as usize;
+    let (mut data, _) = pack.into_inner().unwrap();
+    data.get_mut()[offset] = b'S';
+    pack = CPack::new_from_file(data).unwrap();
+    pack.set_checksums(&provenance);
+    pack
+}
+
+fn is_mismatch<T>(result: Result<T, CPackError>) -> bool {
+    matches!(result, Err(CPackError::ChecksumMismatch(1)))
+}
+
+#[test]
+fn unmodified_files_are_read() {
+    let pack = modified_archive();
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"first file");
+    assert_eq!(pack.read_entry_range(0, 6, 4).unwrap(), b"file");
+    let mut content = Vec::new();
+    pack.get_file(0).unwrap().read_to_end(&mut content).unwrap();
+    assert_eq!(content, b"first file");
+}
+
+#[test]
+fn every_read_verify_the_checksum() {
+    let pack = modified_archive();
+    assert!(is_mismatch(pack.read_file_to_vec(1)));
+    assert!(is_mismatch(pack.read_files(&[0, 1]).remove(1)));
+    assert!(is_mismatch(pack.get_file(1)));
+    assert!(is_mismatch(pack.get_file_cloned(1)));
+    assert!(is_mismatch(pack.read_entry_range(1, 0, 1)));
+    assert!(is_mismatch(pack.entry_chunks(1, 4)));
+    assert!(is_mismatch(pack.hash_all()));
+    assert!(is_mismatch(pack.search(b"file")));
+
+    let mut pack = pack;
+    pack.clear_checksums();
+    assert_eq!(pack.read_entry_range(1, 0, 6).unwrap(), b"Second");
+}
@@ -0,0 +1,267 @@
+//! Committing the modifications staged in a ``CPackEditor``, especially in place
+
+use pmd_cpack::testing::{build_archive, build_archive_with_profile, build_raw_header, MockStorage};
+use pmd_cpack::{CPack, CPackEditor, CPackError, EditDiff, EditOp, EntryChange, GamePreset, HeaderVariant, WriterProfile};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+/// Parse ``data`` as an archive that can be modified in place
+fn open_writable(data: Vec<u8>) -> CPack<Cursor<Vec<u8>>> {
+    CPack::new_from_file(Cursor::new(data)).unwrap()
+}
+
+/// Write ``data`` to a file in a new temporary directory, returning its path
+fn write_temporary(name: &str, data: &[u8]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-editor-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("archive.bin");
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn opened_archive_can_be_committed_in_place() {
+    let path = write_temporary("open-in-place", &build_archive(&[b"first", b"second"]));
+    let mut editor = CPackEditor::open(&path).unwrap();
+    editor.replace(0, b"1st".to_vec()).unwrap();
+    editor.commit_in_place().unwrap();
+    editor.replace(1, b"2nd".to_vec()).unwrap();
+    editor.commit_in_place().unwrap();
+    assert!(!editor.has_changes());
+    let pack = CPack::new_from_file(fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"1st");
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"2nd");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn read_only_archive_is_never_committed_in_place() {
+    let data = build_archive(&[b"first", b"second"]);
+    let path = write_temporary("read-only", &data);
+    let writable = fs::metadata(&path).unwrap().permissions();
+    let mut permissions = writable.clone();
+    permissions.set_readonly(true);
+    fs::set_permissions(&path, permissions).unwrap();
+    let mut editor = CPackEditor::open_read_only(&path).unwrap();
+    editor.replace(0, b"1st".to_vec()).unwrap();
+    assert!(!editor.can_commit_in_place());
+    assert!(matches!(editor.commit_in_place(), Err(CPackError::InPlaceEditImpossible)));
+    assert!(matches!(editor.commit_in_place_truncating(), Err(CPackError::InPlaceEditImpossible)));
+    assert_eq!(fs::read(&path).unwrap(), data);
+    assert!(editor.has_changes());
+    let mut committed = Vec::new();
+    editor.commit(&mut committed, WriterProfile::default()).unwrap();
+    assert_eq!(committed, build_archive(&[b"1st", b"second"]));
+    fs::set_permissions(&path, writable).unwrap();
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn truncating_keep_the_remaining_files() {
+    let mut editor = CPackEditor::new(open_writable(build_archive(&[b"first", b"second", b"third"])));
+    editor.remove(2).unwrap();
+    editor.commit_in_place_truncating().unwrap();
+    let data = editor.into_pack().into_inner().unwrap().0.into_inner();
+    let pack = open_writable(data.clone());
+    assert_eq!(pack.len(), 2);
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"second");
+    assert_eq!(pack.archive_len(), data.len() as u64);
+    assert_eq!(data.len() % 16, 0);
+}
+
+#[test]
+fn truncating_to_empty_files_keep_the_header() {
+    // two empty files pointing at the start of the archive, then a file that is removed
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 3, &[(0, 0), (0, 0), (48, 16)]);
+    data.resize(48, 0xFF);
+    data.extend_from_slice(&[1; 16]);
+    let mut editor = CPackEditor::new(open_writable(data));
+    editor.remove(2).unwrap();
+    editor.commit_in_place_truncating().unwrap();
+    let data = editor.into_pack().into_inner().unwrap().0.into_inner();
+    assert_eq!(data.len(), 32);
+    let pack = open_writable(data);
+    assert_eq!(pack.len(), 2);
+    assert!(pack.read_file_to_vec(0).unwrap().is_empty());
+    assert!(pack.read_file_to_vec(1).unwrap().is_empty());
+}
+
+#[test]
+fn truncating_every_file_leave_an_empty_archive() {
+    let mut editor = CPackEditor::new(open_writable(build_archive(&[b"first", b"second"])));
+    editor.remove(1).unwrap();
+    editor.remove(0).unwrap();
+    editor.commit_in_place_truncating().unwrap();
+    let data = editor.into_pack().into_inner().unwrap().0.into_inner();
+    assert_eq!(data.len(), 16);
+    assert!(open_writable(data).is_empty());
+}
+
+#[test]
+fn failed_truncation_keep_the_modifications_staged() {
+    let storage = MockStorage::new(build_archive(&[b"first", b"second", b"third"])).fail_set_len();
+    let mut editor = CPackEditor::new(CPack::new_from_file(storage).unwrap());
+    editor.remove(2).unwrap();
+    assert!(editor.commit_in_place_truncating().is_err());
+    assert_eq!(editor.len(), 2);
+    assert!(editor.has_changes());
+    // the table of content was cut before the truncation, so the archive is still valid
+    let storage = editor.into_pack().into_inner().unwrap().0;
+    let pack = open_writable(storage.data().to_vec());
+    assert_eq!(pack.len(), 2);
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"second");
+}
+
+#[test]
+fn failed_in_place_commit_keep_the_modifications_staged() {
+    let data = build_archive(&[b"first", b"second"]);
+    let second_offset = open_writable(data.clone()).entry_info(1).unwrap().offset();
+    let storage = MockStorage::new(data).fail_writes_at(second_offset);
+    let mut editor = CPackEditor::new(CPack::new_from_file(storage).unwrap());
+    editor.replace(0, b"1st".to_vec()).unwrap();
+    editor.replace(1, b"2nd".to_vec()).unwrap();
+    assert!(editor.commit_in_place().is_err());
+    assert!(editor.has_changes());
+    assert_eq!(editor.read(0).unwrap().as_ref(), b"1st");
+    assert_eq!(editor.read(1).unwrap().as_ref(), b"2nd");
+    assert_eq!(editor.pack().entry_info(0).unwrap().length(), 5);
+}
+
+#[test]
+fn shrunk_file_is_padded_in_place() {
+    let data = build_archive(&[b"first", b"second"]);
+    let original_len = data.len();
+    let second = open_writable(data.clone()).entry_info(1).unwrap();
+    let mut editor = CPackEditor::new(open_writable(data));
+    editor.replace(1, b"2nd".to_vec()).unwrap();
+    assert!(editor.can_commit_in_place());
+    editor.commit_in_place().unwrap();
+    assert!(editor.changelog().is_empty());
+    let data = editor.into_pack().into_inner().unwrap().0.into_inner();
+    assert_eq!(data.len(), original_len);
+    let start = second.offset() as usize;
+    assert_eq!(&data[start..start + 3], b"2nd");
+    let pack = open_writable(data);
+    assert_eq!(pack.entry_info(1).unwrap().offset(), second.offset());
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"first");
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"2nd");
+}
+
+#[test]
+fn shared_or_growing_file_need_a_full_commit() {
+    // the two files share the same data
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 2, &[(32, 4), (32, 4)]);
+    data.extend_from_slice(b"data");
+    let mut editor = CPackEditor::new(open_writable(data.clone()));
+    editor.replace(0, b"new".to_vec()).unwrap();
+    assert!(!editor.can_commit_in_place());
+    assert!(editor.commit_in_place().is_err());
+    // nothing was written
+    assert_eq!(editor.into_pack().into_inner().unwrap().0.into_inner(), data);
+
+    let mut editor = CPackEditor::new(open_writable(build_archive(&[b"first", b"second"])));
+    editor.replace(0, b"longer first".to_vec()).unwrap();
+    assert!(!editor.can_commit_in_place());
+    let mut out = Vec::new();
+    editor.commit(&mut out, WriterProfile::default()).unwrap();
+    let pack = open_writable(out);
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"longer first");
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"second");
+}
+
+#[test]
+fn staged_content_is_shared_with_the_journal() {
+    let mut editor = CPackEditor::new(open_writable(build_archive(&[b"first"])));
+    editor.replace(0, b"replaced".to_vec()).unwrap();
+    let id = editor.push(b"pushed".to_vec());
+    let staged = editor.read(0).unwrap();
+    match &editor.changelog()[0] {
+        EditOp::Replace { id: 0, data } => assert_eq!(data.as_ptr(), staged.as_ptr()),
+        op => panic!("unexpected operation {:?}", op),
+    };
+    assert_eq!(editor.read(id).unwrap(), &b"pushed"[..]);
+    assert!(editor.undo());
+    assert!(editor.read(id).is_err());
+    assert!(editor.redo());
+    assert_eq!(editor.read(id).unwrap(), &b"pushed"[..]);
+}
+
+#[test]
+fn staged_modifications_are_committed_to_a_new_archive() {
+    let mut editor = CPackEditor::new(open_writable(build_archive(&[b"first", b"second", b"third"])));
+    editor.replace(2, b"3rd".to_vec()).unwrap();
+    editor.remove(0).unwrap();
+    let pushed = editor.push(b"fourth".to_vec());
+    assert_eq!(pushed, 2);
+    assert!(editor.replace(3, Vec::new()).is_err());
+    assert!(editor.has_changes());
+    assert_eq!(editor.read(0).unwrap(), &b"second"[..]);
+    assert_eq!(
+        editor.diff(),
+        EditDiff {
+            entries: vec![EntryChange::Moved(1), EntryChange::Replaced(2), EntryChange::Added],
+            removed: vec![0],
+        }
+    );
+    let mut changelog = Vec::new();
+    editor.write_changelog(&mut changelog).unwrap();
+    assert_eq!(changelog, b"replace 2 3\nremove 0\npush 6\n");
+
+    let mut out = Vec::new();
+    editor.commit(&mut out, WriterProfile::default()).unwrap();
+    assert_eq!(out, build_archive(&[b"second", b"3rd", b"fourth"]));
+}
+
+#[test]
+fn undone_operations_are_not_committed() {
+    let original = build_archive(&[b"first", b"second"]);
+    let mut editor = CPackEditor::new(open_writable(original.clone()));
+    editor.remove(0).unwrap();
+    editor.replace(0, b"2nd".to_vec()).unwrap();
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo());
+    assert!(!editor.has_changes());
+    assert!(editor.diff().is_empty());
+    assert!(editor.redo());
+    let mut out = Vec::new();
+    editor.commit(&mut out, WriterProfile::default()).unwrap();
+    assert_eq!(out, build_archive(&[b"second"]));
+
+    // staging a new operation forget the undone ones
+    editor.push(b"third".to_vec());
+    assert!(!editor.redo());
+    editor.discard();
+    let mut out = Vec::new();
+    editor.commit(&mut out, WriterProfile::default()).unwrap();
+    assert_eq!(out, original);
+}
+
+#[test]
+fn files_are_appended_in_place_when_the_header_has_room() {
+    // the header is padded to 64 bytes, leaving room for four more entries
+    let data = build_archive_with_profile(&[b"first", b"second"], GamePreset::ExplorersOfSky.into());
+    let original_len = data.len();
+    let mut editor = CPackEditor::new(open_writable(data.clone()));
+    editor.push(b"third".to_vec());
+    editor.push(b"fourth".to_vec());
+    assert!(editor.can_commit_in_place());
+    editor.commit_in_place().unwrap();
+    let written = editor.into_pack().into_inner().unwrap().0.into_inner();
+    // the existing files weren't moved
+    assert_eq!(written[64..original_len], data[64..original_len]);
+    let pack = open_writable(written);
+    assert_eq!(pack.len(), 4);
+    assert_eq!(pack.data_start(), 64);
+    assert_eq!(pack.entry_info(2).unwrap().offset(), original_len as u64);
+    assert_eq!(pack.read_file_to_vec(2).unwrap(), b"third");
+    assert_eq!(pack.read_file_to_vec(3).unwrap(), b"fourth");
+
+    let mut editor = CPackEditor::new(open_writable(data));
+    for _ in 0..5 {
+        editor.push(b"too many".to_vec());
+    }
+    assert!(!editor.can_commit_in_place());
+    assert!(matches!(editor.commit_in_place(), Err(CPackError::InPlaceEditImpossible)));
+}
@@ -0,0 +1,70 @@
+//! Archives without any file are valid, and every function should handle them
+
+use pmd_cpack::testing::{build_archive, open_archive, EMPTY};
+use pmd_cpack::{
+    verify_roundtrip, Alignment, CPack, CPackEditor, CPackError, CPackWriter, HeaderVariant, ParseOptions, Strictness,
+    WriterProfile,
+};
+use std::io::Cursor;
+
+#[test]
+fn writer_emit_header_only() {
+    assert_eq!(build_archive(&[]), EMPTY);
+    let profile = WriterProfile {
+        alignment: Alignment { header: 64, entry: 4 },
+        variant: HeaderVariant::BigEndian,
+        ..WriterProfile::default()
+    };
+    let mut out = Vec::new();
+    CPackWriter::new(profile).write(&mut out).unwrap();
+    assert_eq!(out.len(), 64);
+    assert_eq!(&out[..16], &EMPTY);
+}
+
+#[test]
+fn reader_accept_empty_archive() {
+    for strictness in [Strictness::Lax, Strictness::Normal, Strictness::Strict] {
+        for variant in [HeaderVariant::LittleEndian, HeaderVariant::BigEndian] {
+            let options = ParseOptions {
+                strictness,
+                variant,
+                ..ParseOptions::default()
+            };
+            let pack = CPack::new_with_options(Cursor::new(EMPTY), options).unwrap();
+            assert!(pack.is_empty());
+            assert!(pack.warnings().is_empty());
+            assert_eq!(pack.archive_len(), 16);
+        }
+    }
+}
+
+#[test]
+fn read_functions_handle_empty_archive() {
+    let pack = open_archive(&EMPTY);
+    assert!(matches!(pack.get_file(0), Err(CPackError::EntryNotFound(0))));
+    assert!(matches!(pack.read_file_to_vec(0), Err(CPackError::EntryNotFound(0))));
+    assert!(pack.hash_all().unwrap().is_empty());
+    assert!(pack.find_duplicates().unwrap().is_empty());
+    assert!(pack.tree().unwrap().is_empty());
+    assert_eq!(pack.total_payload_len().unwrap(), 0);
+    assert_eq!(pack.total_padding_len().unwrap(), 0);
+    assert_eq!(pack.infer_padding_byte().unwrap(), None);
+    assert_eq!(pack.bytes_iter().count(), 0);
+}
+
+#[test]
+fn empty_archive_roundtrip() {
+    let pack = open_archive(&EMPTY);
+    let report = verify_roundtrip(&pack, WriterProfile::default()).unwrap();
+    assert!(report.first_difference.is_none());
+}
+
+#[test]
+fn files_can_be_added_to_empty_archive() {
+    let mut editor = CPackEditor::new(open_archive(&EMPTY));
+    editor.push(b"hello".to_vec());
+    let mut out = Vec::new();
+    editor.commit(&mut out, WriterProfile::default()).unwrap();
+    let pack = open_archive(&out);
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"hello");
+}
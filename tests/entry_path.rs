@@ -0,0 +1,43 @@
+//! Referencing a file inside an archive on disk with [`EntryPath`]
+
+use pmd_cpack::testing::build_archive;
+use pmd_cpack::{CPackError, EntryId, EntryPath};
+use std::fs;
+use std::process;
+
+#[test]
+fn both_forms_are_parsed() {
+    let expected = EntryPath::new("MONSTER/m_attack.bin", EntryId(42));
+    assert_eq!("MONSTER/m_attack.bin#42".parse::<EntryPath>().unwrap(), expected);
+    assert_eq!("cpack://MONSTER/m_attack.bin/42".parse::<EntryPath>().unwrap(), expected);
+    // only the last separator is used, so it may appear in the path
+    assert_eq!(
+        "dir#1/archive.bin#2".parse::<EntryPath>().unwrap(),
+        EntryPath::new("dir#1/archive.bin", EntryId(2))
+    );
+    assert_eq!(expected.to_string(), "MONSTER/m_attack.bin#42");
+    assert_eq!(expected.to_string().parse::<EntryPath>().unwrap(), expected);
+}
+
+#[test]
+fn invalid_references_are_refused() {
+    for text in ["archive.bin", "#3", "archive.bin#", "archive.bin#-1", "archive.bin#0x2", "cpack://3", "cpack://a.bin/x"] {
+        match text.parse::<EntryPath>() {
+            Err(CPackError::InvalidEntryPath(invalid)) => assert_eq!(invalid, text),
+            other => panic!("{:?} gave {:?}", text, other),
+        };
+    }
+}
+
+#[test]
+fn referenced_file_is_read() {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-entry-path-{}", process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("archive.bin"), build_archive(&[b"first", b"second"])).unwrap();
+    let path: EntryPath = "archive.bin#1".parse().unwrap();
+    let path = path.relative_to(&directory);
+    assert_eq!(path.archive, directory.join("archive.bin"));
+    assert_eq!(path.read().unwrap(), b"second");
+    assert!(EntryPath::new(path.archive.clone(), EntryId(2)).read().is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
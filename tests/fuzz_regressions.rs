@@ -0,0 +1,43 @@
+//! Replay the archives of ``fuzz/regressions``, that caused panics in the past or hit the limits of the format, through
+//! the same checks as the fuzz targets. Add the crashing inputs found by ``cargo fuzz`` there once fixed.
+
+use pmd_cpack::testing::exercise_read_api;
+use pmd_cpack::CPack;
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+/// The archives of the corpus that are valid, with their number of file
+const VALID: [(&str, usize); 4] = [
+    ("no_file.cpack", 0),
+    ("zero_length_files.cpack", 3),
+    ("zero_length_file_at_end.cpack", 2),
+    ("file_inside_header.cpack", 1),
+];
+
+fn regressions_directory() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fuzz").join("regressions")
+}
+
+#[test]
+fn regressions_never_panic() {
+    let mut count = 0;
+    for entry in fs::read_dir(regressions_directory()).unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        exercise_read_api(&data);
+        count += 1;
+    }
+    assert!(count >= VALID.len());
+}
+
+#[test]
+fn valid_regressions_parse() {
+    for (name, len) in VALID {
+        let data = fs::read(regressions_directory().join(name)).unwrap();
+        let pack = CPack::new_from_file(Cursor::new(data)).unwrap();
+        assert_eq!(pack.len(), len, "{}", name);
+        for id in 0..len {
+            pack.read_file_to_vec(id).unwrap();
+        }
+    }
+}
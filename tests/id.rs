@@ -0,0 +1,30 @@
+//! Addressing files with [`EntryId`]
+
+use pmd_cpack::testing::build_archive;
+use pmd_cpack::{CPack, EntryId};
+use std::io::{Cursor, Read};
+
+#[test]
+fn id_is_written_and_parsed_as_a_number() {
+    assert_eq!(EntryId(42).to_string(), "42");
+    assert_eq!("42".parse::<EntryId>().unwrap(), EntryId(42));
+    assert!("-1".parse::<EntryId>().is_err());
+    assert!("0x2a".parse::<EntryId>().is_err());
+    assert_eq!(usize::from(EntryId::from(7)), 7);
+}
+
+#[test]
+fn accessors_take_an_id_or_an_usize() {
+    let pack = CPack::new_from_file(Cursor::new(build_archive(&[b"first", b"second"]))).unwrap();
+    assert_eq!(pack.ids().collect::<Vec<_>>(), [EntryId(0), EntryId(1)]);
+    for id in pack.ids() {
+        assert_eq!(pack.read_file_by_id(id).unwrap(), pack.read_file_to_vec(id.index()).unwrap());
+        assert_eq!(pack.entry_info_by_id(id), pack.entry_info(id.index()));
+    }
+    assert_eq!(pack.read_file_by_id(1usize).unwrap(), b"second");
+    let mut content = Vec::new();
+    pack.get_file_by_id(EntryId(0)).unwrap().read_to_end(&mut content).unwrap();
+    assert_eq!(content, b"first");
+    assert!(pack.entry_info_by_id(EntryId(2)).is_none());
+    assert!(pack.read_file_by_id(EntryId(2)).is_err());
+}
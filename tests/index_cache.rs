@@ -0,0 +1,98 @@
+//! Reopening archives with ``CPack::open_with_index_cache``
+
+use pmd_cpack::{CPack, CPackError, CPackIndex, CPackWriter, ParseOptions, Strictness, WriterProfile};
+use std::fs;
+use std::path::PathBuf;
+
+/// Write an archive of three files in a new temporary directory, returning its path
+fn write_archive(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-index-cache-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("archive.bin");
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    for file in [&b"first"[..], b"second", b"third"] {
+        writer.push(file.to_vec());
+    }
+    let mut out = Vec::new();
+    writer.write(&mut out).unwrap();
+    fs::write(&path, out).unwrap();
+    path
+}
+
+fn strict() -> ParseOptions {
+    ParseOptions {
+        strictness: Strictness::Strict,
+        ..ParseOptions::default()
+    }
+}
+
+#[test]
+fn cache_is_written_then_used() {
+    let path = write_archive("hit");
+    let cache_path = CPackIndex::cache_path(&path);
+    CPack::open_with_index_cache_and_options(&path, strict()).unwrap();
+    assert!(cache_path.exists());
+
+    let pack = CPack::open_with_index_cache_and_options(&path, strict()).unwrap();
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"second");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn padding_modified_after_caching_is_still_checked() {
+    let path = write_archive("padding");
+    CPack::open_with_index_cache_and_options(&path, strict()).unwrap();
+    // the padding isn't covered by the hash of the header
+    let mut data = fs::read(&path).unwrap();
+    let last = data.len() - 1;
+    data[last] = 0;
+    fs::write(&path, &data).unwrap();
+    assert!(matches!(
+        CPack::open_with_index_cache_and_options(&path, strict()),
+        Err(CPackError::InconsistentPadding(_, 0xFF, 0))
+    ));
+    // the padding is only checked by strict parsing
+    let pack = CPack::open_with_index_cache(&path).unwrap();
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"second");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn cache_of_another_length_is_ignored() {
+    let path = write_archive("length");
+    CPack::open_with_index_cache(&path).unwrap();
+    let mut data = fs::read(&path).unwrap();
+    data.extend_from_slice(&[0xFF; 16]);
+    fs::write(&path, &data).unwrap();
+    let pack = CPack::open_with_index_cache(&path).unwrap();
+    assert_eq!(pack.archive_len(), data.len() as u64);
+    // the cache was replaced by one of the new archive
+    let pack = CPack::open_with_index_cache(&path).unwrap();
+    assert_eq!(pack.archive_len(), data.len() as u64);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn invalid_cache_is_ignored() {
+    let path = write_archive("invalid");
+    let cache_path = CPackIndex::cache_path(&path);
+    fs::write(&cache_path, b"not an index cache").unwrap();
+    let pack = CPack::open_with_index_cache(&path).unwrap();
+    assert_eq!(pack.len(), 3);
+    assert_ne!(fs::read(&cache_path).unwrap(), b"not an index cache");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn cache_with_a_header_past_the_end_is_ignored() {
+    let path = write_archive("header-len");
+    let cache_path = CPackIndex::cache_path(&path);
+    CPack::open_with_index_cache(&path).unwrap();
+    // the header length follow the 8 bytes magic and the 32 bytes hash
+    let mut cache = fs::read(&cache_path).unwrap();
+    cache[40..48].copy_from_slice(&u64::MAX.to_le_bytes());
+    fs::write(&cache_path, &cache).unwrap();
+    let pack = CPack::open_with_index_cache(&path).unwrap();
+    assert_eq!(pack.read_file_to_vec(2).unwrap(), b"third");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
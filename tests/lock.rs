@@ -0,0 +1,51 @@
+//! Committing in place while holding a lock on the archive, with the ``file-lock`` feature
+
+use pmd_cpack::testing::build_archive;
+use pmd_cpack::{CPack, CPackEditor, CPackError};
+use std::fs;
+use std::path::PathBuf;
+
+/// Write ``data`` to a file in a new temporary directory, returning its path
+fn write_temporary(name: &str, data: &[u8]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-lock-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("archive.bin");
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn locked_archive_is_not_modified() {
+    let data = build_archive(&[b"first", b"second"]);
+    let path = write_temporary("locked", &data);
+    let other = fs::File::open(&path).unwrap();
+    other.lock().unwrap();
+    let mut editor = CPackEditor::open(&path).unwrap();
+    editor.replace(0, b"1st".to_vec()).unwrap();
+    assert!(matches!(editor.commit_in_place_locked(), Err(CPackError::ArchiveLocked)));
+    assert_eq!(fs::read(&path).unwrap(), data);
+    other.unlock().unwrap();
+    editor.commit_in_place_locked().unwrap();
+    assert_eq!(CPack::new_from_file(fs::File::open(&path).unwrap()).unwrap().read_file_to_vec(0).unwrap(), b"1st");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn archive_modified_since_opened_is_not_overwritten() {
+    let data = build_archive(&[b"first", b"second"]);
+    let path = write_temporary("modified", &data);
+    let mut first = CPackEditor::open(&path).unwrap();
+    let mut second = CPackEditor::open(&path).unwrap();
+    first.replace(0, b"1st".to_vec()).unwrap();
+    second.replace(1, b"2nd".to_vec()).unwrap();
+    first.commit_in_place_locked().unwrap();
+    let committed = fs::read(&path).unwrap();
+    assert!(matches!(second.commit_in_place_locked(), Err(CPackError::ArchiveModified)));
+    assert_eq!(fs::read(&path).unwrap(), committed);
+    first.replace(1, b"2nd".to_vec()).unwrap();
+    first.commit_in_place_locked().unwrap();
+    let pack = CPack::new_from_file(fs::File::open(&path).unwrap()).unwrap();
+    assert_eq!(pack.read_file_to_vec(0).unwrap(), b"1st");
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"2nd");
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
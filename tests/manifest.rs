@@ -0,0 +1,127 @@
+//! Rebuilding an extracted archive byte for byte from its ``MANIFEST``
+
+use pmd_cpack::testing::{build_archive, build_raw_header};
+use pmd_cpack::{pack_from_manifest, CPack, CPackError, ExtractOptions, HeaderVariant};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::process;
+
+/// Return a new empty directory for the test ``name``
+fn test_dir(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("pmd_cpack-manifest-{}-{}", process::id(), name));
+    fs::remove_dir_all(&directory).ok();
+    directory
+}
+
+/// Extract ``data`` with a manifest to a new directory, returning it
+fn extract(name: &str, data: &[u8]) -> PathBuf {
+    let directory = test_dir(name);
+    let pack = CPack::new_from_file(Cursor::new(data.to_vec())).unwrap();
+    pack.extract_all_with_options(&directory, &ExtractOptions::with_manifest()).unwrap();
+    directory
+}
+
+fn rebuild(directory: &PathBuf) -> Result<Vec<u8>, CPackError> {
+    let mut out = Vec::new();
+    pack_from_manifest(directory, &mut out)?;
+    Ok(out)
+}
+
+/// Return an archive of two files, with padding of different bytes between them, followed by ``trailing``
+fn unusual_archive(trailing: &[u8]) -> Vec<u8> {
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 2, &[(32, 5), (48, 6)]);
+    data.extend_from_slice(b"first");
+    data.extend((0..11).map(|n| n as u8));
+    data.extend_from_slice(b"second");
+    data.extend_from_slice(trailing);
+    data
+}
+
+#[test]
+fn written_archive_is_rebuilt() {
+    let data = build_archive(&[b"first", b"", b"third file"]);
+    let directory = extract("written", &data);
+    assert_eq!(rebuild(&directory).unwrap(), data);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn padding_and_trailing_data_are_rebuilt() {
+    // bigger than the chunks the padding is read by
+    let trailing: Vec<u8> = (0..0x18000).map(|n| (n % 251) as u8).collect();
+    let data = unusual_archive(&trailing);
+    let directory = extract("trailing", &data);
+    let manifest = fs::read_to_string(directory.join("MANIFEST")).unwrap();
+    assert!(manifest.contains("data\t37\t11\tpadding_37.bin\n"));
+    assert_eq!(fs::read(directory.join("padding_54.bin")).unwrap(), trailing);
+    assert_eq!(rebuild(&directory).unwrap(), data);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn repeated_byte_is_a_fill() {
+    let mut trailing = vec![0xAA; 0x18000];
+    let data = unusual_archive(&trailing);
+    let directory = extract("fill", &data);
+    let manifest = fs::read_to_string(directory.join("MANIFEST")).unwrap();
+    assert!(manifest.contains("fill\t54\t98304\taa\n"));
+    assert!(!directory.join("padding_54.bin").exists());
+    assert_eq!(rebuild(&directory).unwrap(), data);
+    fs::remove_dir_all(&directory).unwrap();
+
+    // a single different byte after the first chunk
+    *trailing.last_mut().unwrap() = 0xAB;
+    let data = unusual_archive(&trailing);
+    let directory = extract("fill-last", &data);
+    assert_eq!(fs::read(directory.join("padding_54.bin")).unwrap(), trailing);
+    assert_eq!(rebuild(&directory).unwrap(), data);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn invalid_manifest_is_reported_with_its_line() {
+    let directory = extract("invalid", &build_archive(&[b"first"]));
+    let manifest = fs::read_to_string(directory.join("MANIFEST")).unwrap();
+    let mut lines: Vec<&str> = manifest.lines().collect();
+    lines[2] = "length\tlong";
+    fs::write(directory.join("MANIFEST"), lines.join("\n")).unwrap();
+    assert!(matches!(rebuild(&directory), Err(CPackError::InvalidManifest(3))));
+
+    fs::write(directory.join("MANIFEST"), "not a manifest\n").unwrap();
+    assert!(matches!(rebuild(&directory), Err(CPackError::InvalidManifest(1))));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn file_outside_the_directory_is_refused() {
+    let directory = extract("outside", &build_archive(&[b"first"]));
+    let manifest = fs::read_to_string(directory.join("MANIFEST")).unwrap();
+    let line = manifest.lines().count() + 1;
+    for name in ["../MANIFEST", "/etc/passwd", "sub\\0.bin", ".."] {
+        for extra in [format!("header\t{}", name), format!("data\t0\t1\t{}", name)] {
+            fs::write(directory.join("MANIFEST"), format!("{}{}\n", manifest, extra)).unwrap();
+            assert!(matches!(rebuild(&directory), Err(CPackError::InvalidManifest(l)) if l == line), "{}", extra);
+        }
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn part_after_the_end_of_the_archive_is_refused() {
+    let directory = extract("after-end", &build_archive(&[b"first"]));
+    let manifest = fs::read_to_string(directory.join("MANIFEST")).unwrap();
+    fs::write(directory.join("MANIFEST"), format!("{}fill\t0\t{}\taa\n", manifest, u64::MAX)).unwrap();
+    let mut out = Vec::new();
+    assert!(pack_from_manifest(&directory, &mut out).is_err());
+    assert!(out.is_empty());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn resized_file_is_refused() {
+    let directory = extract("resized", &build_archive(&[b"first", b"second"]));
+    fs::write(directory.join("0.bin"), b"longer first").unwrap();
+    assert!(rebuild(&directory).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
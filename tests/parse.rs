@@ -0,0 +1,155 @@
+//! Parsing the header of archives, including invalid or unusual ones
+
+use pmd_cpack::testing::{build_archive, build_raw_header, BAD_MAGIC};
+use pmd_cpack::{CPack, CPackError, HeaderVariant, ParseOptions, ParseWarning, Strictness};
+use std::io::{self, Cursor};
+
+#[test]
+fn length_above_4_gib_is_not_truncated() {
+    // with the length truncated to 32 bits, this archive would be 24 bytes long, and the file would end after it
+    let header = build_raw_header(HeaderVariant::LittleEndian, 1, &[(24, 100)]);
+    let pack = CPack::new_with_len(Cursor::new(header.clone()), (1 << 32) + 24).unwrap();
+    assert_eq!(pack.archive_len(), (1 << 32) + 24);
+
+    let header = build_raw_header(HeaderVariant::LittleEndian, 1, &[(u32::MAX, u32::MAX)]);
+    match CPack::new_with_len(Cursor::new(header), (1 << 32) + 24) {
+        Err(CPackError::EndOfFileOutOfScope(0, end, archive_end, _)) => {
+            assert_eq!(end, u32::MAX as u64 * 2);
+            assert_eq!(archive_end, (1 << 32) + 24);
+        }
+        other => panic!("unexpected result: {:?}", other.map(|pack| pack.len())),
+    };
+}
+
+#[test]
+fn skipping_validation_check_the_number_of_file() {
+    let options = ParseOptions {
+        skip_validation: true,
+        ..ParseOptions::default()
+    };
+    // a table of content of 32 GiB is declared, but not allocated
+    let header = build_raw_header(HeaderVariant::LittleEndian, u32::MAX, &[(16, 0)]);
+    match CPack::new_with_options(Cursor::new(header), options.clone()) {
+        Err(CPackError::IOError(err)) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+        other => panic!("unexpected result: {:?}", other.map(|pack| pack.len())),
+    };
+
+    let header = build_raw_header(HeaderVariant::LittleEndian, 1, &[(24, 0)]);
+    let pack = CPack::new_with_options(Cursor::new(header), options).unwrap();
+    assert_eq!(pack.len(), 1);
+}
+
+fn with_strictness(strictness: Strictness) -> ParseOptions {
+    ParseOptions {
+        strictness,
+        ..ParseOptions::default()
+    }
+}
+
+#[test]
+fn trailing_data_is_a_warning() {
+    let mut data = build_archive(&[b"first", b"second"]);
+    let archive_end = data.len() as u64;
+    data.extend_from_slice(&[0xFF; 20]);
+    for strictness in [Strictness::Lax, Strictness::Normal, Strictness::Strict] {
+        let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(strictness)).unwrap();
+        assert_eq!(pack.warnings(), &[ParseWarning::TrailingData(archive_end, 20)]);
+    }
+}
+
+#[test]
+fn padding_is_only_read_when_strict() {
+    let mut data = build_archive(&[b"first", b"second"]);
+    let last = data.len() - 1;
+    data[last] = 0;
+    let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(Strictness::Normal)).unwrap();
+    assert!(pack.warnings().is_empty());
+    assert_eq!(pack.check_padding().unwrap(), Some(ParseWarning::InconsistentPadding(last as u64, 0xFF, 0)));
+    match CPack::new_with_options(Cursor::new(data), with_strictness(Strictness::Strict)) {
+        Err(CPackError::InconsistentPadding(offset, 0xFF, 0)) => assert_eq!(offset, last as u64),
+        other => panic!("unexpected result: {:?}", other.map(|pack| pack.len())),
+    };
+}
+
+#[test]
+fn big_padding_is_read_by_chunks() {
+    // a single file after 1 MiB of padding, with a different byte at the very end of the padding
+    let data_start = 0x100000;
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 1, &[(data_start, 4)]);
+    let header_len = data.len() as u64;
+    data.resize(data_start as usize, 0xFF);
+    data[data_start as usize - 1] = 0;
+    data.extend_from_slice(b"file");
+    let (_, report) = CPack::parse_with_report(Cursor::new(data.clone()), with_strictness(Strictness::Normal)).unwrap();
+    assert_eq!(report.bytes_consumed, header_len);
+    let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(Strictness::Normal)).unwrap();
+    assert_eq!(
+        pack.check_padding().unwrap(),
+        Some(ParseWarning::InconsistentPadding(data_start as u64 - 1, 0xFF, 0))
+    );
+
+    data[data_start as usize - 1] = 0xFF;
+    let (_, report) = CPack::parse_with_report(Cursor::new(data), with_strictness(Strictness::Strict)).unwrap();
+    assert_eq!(report.bytes_consumed, data_start as u64);
+}
+
+#[test]
+fn bad_magic_is_only_accepted_when_lax() {
+    let pack = CPack::new_with_options(Cursor::new(BAD_MAGIC), with_strictness(Strictness::Lax)).unwrap();
+    assert_eq!(pack.warnings(), &[ParseWarning::FourFirstByteNotZero([1, 2, 3, 4])]);
+    for strictness in [Strictness::Normal, Strictness::Strict] {
+        let result = CPack::new_with_options(Cursor::new(BAD_MAGIC), with_strictness(strictness));
+        assert!(matches!(result, Err(CPackError::FourFirstByteNotZero([1, 2, 3, 4], _))));
+    }
+}
+
+#[test]
+fn file_after_the_end_is_only_accepted_when_lax() {
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 1, &[(24, 100)]);
+    data.extend_from_slice(b"file");
+    let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(Strictness::Lax)).unwrap();
+    assert_eq!(pack.warnings(), &[ParseWarning::EndOfFileOutOfScope(0)]);
+    for strictness in [Strictness::Normal, Strictness::Strict] {
+        let result = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(strictness));
+        assert!(matches!(result, Err(CPackError::EndOfFileOutOfScope(0, 124, 28, _))));
+    }
+}
+
+#[test]
+fn unterminated_header_is_only_accepted_when_lax() {
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 1, &[(32, 4)]);
+    data[16..24].copy_from_slice(&[1; 8]);
+    data.resize(32, 0xFF);
+    data.extend_from_slice(b"file");
+    let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(Strictness::Lax)).unwrap();
+    assert_eq!(pack.warnings(), &[ParseWarning::EndOfHeaderNotZero([1; 8])]);
+    for strictness in [Strictness::Normal, Strictness::Strict] {
+        let result = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(strictness));
+        assert!(matches!(result, Err(CPackError::EndOfHeaderNotZero(24, [1, 1, 1, 1, 1, 1, 1, 1], _))));
+    }
+}
+
+#[test]
+fn file_overlapping_the_header_is_an_error_when_strict() {
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 1, &[(8, 8)]);
+    data.resize(32, 0xFF);
+    for strictness in [Strictness::Lax, Strictness::Normal] {
+        let pack = CPack::new_with_options(Cursor::new(data.clone()), with_strictness(strictness)).unwrap();
+        assert!(pack.warnings().contains(&ParseWarning::EntryOverlapHeader(0)));
+    }
+    let result = CPack::new_with_options(Cursor::new(data), with_strictness(Strictness::Strict));
+    assert!(matches!(result, Err(CPackError::EntryOverlapHeader(0))));
+}
+
+#[test]
+fn empty_files_are_ignored_for_the_start_of_the_data() {
+    // the empty files point at the start of the archive and at its end
+    let mut data = build_raw_header(HeaderVariant::LittleEndian, 3, &[(0, 0), (40, 5), (45, 0)]);
+    data.extend_from_slice(b"first");
+    let pack = CPack::new_from_file(Cursor::new(data)).unwrap();
+    assert_eq!(pack.data_start(), 40);
+
+    let data = build_raw_header(HeaderVariant::LittleEndian, 2, &[(0, 0), (0, 0)]);
+    let pack = CPack::new_from_file(Cursor::new(data)).unwrap();
+    assert_eq!(pack.data_start(), pack.header_len());
+}
@@ -0,0 +1,96 @@
+//! Reading several files at once with [`CPack::read_files`]
+
+use pmd_cpack::testing::{build_archive, build_sequential_archive, open_archive};
+use pmd_cpack::{CPack, CPackError, IoObserver, ParseOptions, Strictness};
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+/// Count the reads made on the archive
+struct ReadCounter(AtomicUsize);
+
+impl IoObserver for ReadCounter {
+    fn on_read(&self, _offset: u64, _len: u64) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// An observer that panic on the first read, while the archive is locked
+struct PanicOnRead;
+
+impl IoObserver for PanicOnRead {
+    fn on_read(&self, _offset: u64, _len: u64) {
+        panic!("read while testing a poisoned lock");
+    }
+}
+
+#[test]
+fn results_match_reading_one_by_one() {
+    let pack = open_archive(&build_sequential_archive(10));
+    let ids = [7, 2, 9, 2, 0, 5, 7];
+    let results = pack.read_files(&ids);
+    assert_eq!(results.len(), ids.len());
+    for (id, result) in ids.iter().zip(results) {
+        assert_eq!(result.unwrap(), pack.read_file_to_vec(*id).unwrap());
+    }
+}
+
+#[test]
+fn invalid_id_fail_alone() {
+    let pack = open_archive(&build_archive(&[b"first", b"second"]));
+    let mut results = pack.read_files(&[1, 2, 0]);
+    assert!(matches!(results[1], Err(CPackError::EntryNotFound(2))));
+    assert_eq!(results.remove(0).unwrap(), b"second");
+    assert_eq!(results.remove(1).unwrap(), b"first");
+}
+
+#[test]
+fn file_above_read_limit_fail_alone() {
+    let mut pack = open_archive(&build_archive(&[b"short", b"way longer file"]));
+    pack.set_read_limit(Some(8));
+    let mut results = pack.read_files(&[0, 1]);
+    assert!(matches!(results[1], Err(CPackError::ReadLimitExceeded(..))));
+    assert_eq!(results.remove(0).unwrap(), b"short");
+}
+
+#[test]
+fn close_files_are_read_at_once() {
+    let mut pack = open_archive(&build_sequential_archive(10));
+    let counter = Arc::new(ReadCounter::default());
+    pack.set_observer(counter.clone());
+    for result in pack.read_files(&[1, 3, 5, 7, 9]) {
+        result.unwrap();
+    }
+    assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn truncated_file_fail_alone_in_lax_mode() {
+    let mut data = build_archive(&[b"first", b"second", b"third"]);
+    let pack = open_archive(&data);
+    let last = pack.entry_info(2).unwrap();
+    data.truncate(last.offset() as usize + 2);
+    let options = ParseOptions {
+        strictness: Strictness::Lax,
+        ..ParseOptions::default()
+    };
+    let pack = CPack::new_with_options(Cursor::new(data), options).unwrap();
+    let mut results = pack.read_files(&[2, 0, 1]);
+    assert!(matches!(results[0], Err(CPackError::IOError(_))));
+    assert_eq!(results.remove(1).unwrap(), b"first");
+    assert_eq!(results.remove(1).unwrap(), b"second");
+}
+
+#[test]
+fn lock_error_is_returned_for_every_file() {
+    let mut pack = open_archive(&build_archive(&[b"first", b"second"]));
+    pack.set_observer(Arc::new(PanicOnRead));
+    let read = panic::catch_unwind(AssertUnwindSafe(|| pack.read_file_to_vec(0)));
+    assert!(read.is_err());
+    pack.clear_observer();
+    for result in pack.read_files(&[0, 1]) {
+        assert!(matches!(result, Err(CPackError::PoisonedLock)));
+    }
+}
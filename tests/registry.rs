@@ -0,0 +1,66 @@
+//! Reading and writing an [`ArchiveRegistry`]
+
+use pmd_cpack::{ArchiveRegistry, CPackError, KnownArchive};
+use std::collections::BTreeMap;
+
+const REGISTRY: &str = "pmd_cpack-registry 1
+# the effects of Explorers of Sky
+archive\tEFFECT/effect.bin\t*\tthe sprites of the effects
+
+0\tfirst effect
+312\tsome effect
+archive\tmonster.bin\t3\tthe monsters
+1\tbulbasaur
+";
+
+fn read(text: &str) -> Result<ArchiveRegistry, CPackError> {
+    ArchiveRegistry::read(text.as_bytes())
+}
+
+#[test]
+fn archives_and_names_are_read() {
+    let registry = read(REGISTRY).unwrap();
+    assert_eq!(registry.archives().len(), 2);
+    let effects = registry.find("rom/data/effect/EFFECT.BIN", 1000).unwrap();
+    assert_eq!(effects.description, "the sprites of the effects");
+    assert_eq!(effects.entry_name(312), Some("some effect"));
+    assert_eq!(effects.entry_name(1), None);
+    assert!(registry.find("rom/data/other/effect.bin", 1000).is_none());
+    assert!(registry.find("monster.bin", 3).is_some());
+    assert!(registry.find("monster.bin", 4).is_none());
+}
+
+#[test]
+fn invalid_line_is_reported() {
+    assert!(matches!(read("not a registry\n"), Err(CPackError::InvalidRegistry(1))));
+    let cases = [
+        "pmd_cpack-registry 1\n0\tfile before any archive\n",
+        "pmd_cpack-registry 1\narchive\ta.bin\tmany\tcount isn't a number\n",
+        "pmd_cpack-registry 1\narchive\ta.bin\t*\tmissing name\n-1\tnegative id\n",
+        "pmd_cpack-registry 1\narchive\ta.bin\t*\ttoo many fields\n1\tname\textra\n",
+    ];
+    for (case, text) in cases.iter().enumerate() {
+        let expected_line = if case == 0 || case == 1 { 2 } else { 3 };
+        match read(text) {
+            Err(CPackError::InvalidRegistry(line)) => assert_eq!(line, expected_line, "{:?}", text),
+            other => panic!("{:?} gave {:?}", text, other),
+        };
+    }
+}
+
+#[test]
+fn special_characters_are_kept() {
+    let mut entries = BTreeMap::new();
+    entries.insert(3, "name\twith a tab".to_string());
+    entries.insert(4, "two\nlines \\n".to_string());
+    let mut registry = ArchiveRegistry::new();
+    registry.add(KnownArchive {
+        path: "dir\\file.bin".to_string(),
+        entry_count: Some(5),
+        description: "# not a comment\r\n".to_string(),
+        entries,
+    });
+    let mut written = Vec::new();
+    registry.write(&mut written).unwrap();
+    assert_eq!(ArchiveRegistry::read(&written[..]).unwrap(), registry);
+}
@@ -0,0 +1,52 @@
+//! Searching a pattern in the files of an archive with [`CPack::search`]
+
+use pmd_cpack::testing::{build_archive, open_archive, MockStorage};
+use pmd_cpack::CPack;
+
+/// The number of bytes the files are read by, as in the implementation
+const CHUNK_LEN: usize = 0x10000;
+
+/// Return every occurrence of ``pattern`` in ``files``, searched the naive way
+fn naive_search(files: &[&[u8]], pattern: &[u8]) -> Vec<(usize, u64)> {
+    let mut result = Vec::new();
+    for (id, file) in files.iter().enumerate() {
+        for (position, window) in file.windows(pattern.len()).enumerate() {
+            if window == pattern {
+                result.push((id, position as u64));
+            };
+        }
+    }
+    result
+}
+
+/// Return a file of ``len`` bytes of zero with ``pattern`` written at each of ``positions``
+fn file_with(len: usize, pattern: &[u8], positions: &[usize]) -> Vec<u8> {
+    let mut file = vec![0; len];
+    for position in positions {
+        file[*position..*position + pattern.len()].copy_from_slice(pattern);
+    }
+    file
+}
+
+#[test]
+fn occurrences_across_chunks_are_found_once() {
+    let pattern = b"SIR0";
+    let positions = [0, CHUNK_LEN - 3, CHUNK_LEN + 1, 2 * CHUNK_LEN - 1, 2 * CHUNK_LEN + 100 - 4];
+    let big = file_with(2 * CHUNK_LEN + 100, pattern, &positions);
+    let files: [&[u8]; 3] = [b"SIR", &big, b"SIR0SIR0"];
+    let pack = open_archive(&build_archive(&files));
+    let found = pack.search(pattern).unwrap();
+    assert_eq!(found, naive_search(&files, pattern));
+    assert_eq!(found.iter().filter(|(id, _)| *id == 1).count(), positions.len());
+}
+
+#[test]
+fn short_reads_and_overlaps_are_handled() {
+    let files: [&[u8]; 2] = [b"aaaaaaa", b"abababa aba"];
+    let storage = MockStorage::new(build_archive(&files)).with_short_reads(3);
+    let pack = CPack::new_from_file(storage).unwrap();
+    for pattern in [&b"aa"[..], b"aba", b"a", b"abababa aba", b"missing"] {
+        assert_eq!(pack.search(pattern).unwrap(), naive_search(&files, pattern), "{:?}", pattern);
+    }
+    assert!(pack.search(b"").unwrap().is_empty());
+}
@@ -0,0 +1,89 @@
+//! Converting archives to and from tar streams
+
+use pmd_cpack::{pack_from_tar, CPack, CPackError, CPackWriter, WriterProfile};
+use std::io::{Cursor, Read};
+
+fn archive(files: &[&[u8]]) -> Vec<u8> {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    for file in files {
+        writer.push(file.to_vec());
+    }
+    let mut out = Vec::new();
+    writer.write(&mut out).unwrap();
+    out
+}
+
+/// Build a tar stream containing a file for each (name, content)
+fn tar_stream(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, content) in files {
+        let mut header = tar::Header::new_ustar();
+        header.set_size(content.len() as u64);
+        builder.append_data(&mut header, name, *content).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn tar_roundtrip() {
+    let original = archive(&[b"first", b"", b"third"]);
+    let pack = CPack::new_from_file(Cursor::new(original.clone())).unwrap();
+    let mut stream = Vec::new();
+    pack.extract_to_tar(&mut stream).unwrap();
+    let mut out = Vec::new();
+    pack_from_tar(&stream[..]).unwrap().write(&mut out).unwrap();
+    assert_eq!(out, original);
+}
+
+#[test]
+fn files_may_be_in_any_order() {
+    let stream = tar_stream(&[("dir/1.bin", b"second"), ("0", b"first")]);
+    let mut out = Vec::new();
+    pack_from_tar(&stream[..]).unwrap().write(&mut out).unwrap();
+    assert_eq!(out, archive(&[b"first", b"second"]));
+}
+
+#[test]
+fn missing_and_invalid_files_are_rejected() {
+    let stream = tar_stream(&[("0.bin", b"first"), ("2.bin", b"third")]);
+    assert!(matches!(pack_from_tar(&stream[..]), Err(CPackError::MissingTarEntry(1))));
+    let stream = tar_stream(&[("0.bin", b"first"), ("readme.txt", b"hello")]);
+    assert!(matches!(pack_from_tar(&stream[..]), Err(CPackError::InvalidTarEntry(name)) if name == "readme.txt"));
+}
+
+#[test]
+fn huge_declared_size_is_not_allocated() {
+    let mut header = tar::Header::new_ustar();
+    header.set_path("0.bin").unwrap();
+    header.set_size(1 << 40);
+    header.set_cksum();
+    let mut stream = header.as_bytes().to_vec();
+    stream.extend_from_slice(&[0; 1024]);
+    assert!(matches!(pack_from_tar(&stream[..]), Err(CPackError::OffsetOverflow(size)) if size == 1 << 40));
+}
+
+#[test]
+fn extracted_stream_is_deterministic() {
+    let pack = CPack::new_from_file(Cursor::new(archive(&[b"first", b"second"]))).unwrap();
+    let mut stream = Vec::new();
+    pack.extract_to_tar(&mut stream).unwrap();
+    let mut again = Vec::new();
+    pack.extract_to_tar(&mut again).unwrap();
+    assert_eq!(stream, again);
+
+    let mut files = Vec::new();
+    for entry in tar::Archive::new(&stream[..]).entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let header = entry.header();
+        assert_eq!(header.mode().unwrap(), 0o644);
+        assert_eq!(header.mtime().unwrap(), 0);
+        let name = entry.path().unwrap().to_string_lossy().into_owned();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        files.push((name, content));
+    }
+    assert_eq!(
+        files,
+        [("0.bin".to_string(), b"first".to_vec()), ("1.bin".to_string(), b"second".to_vec())]
+    );
+}
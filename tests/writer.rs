@@ -0,0 +1,99 @@
+//! The layout of the archives written by [`CPackWriter`]
+
+use pmd_cpack::testing::build_archive_with_profile;
+use pmd_cpack::{
+    Alignment, CPack, CPackError, CPackWriter, EntrySource, GamePreset, HeaderVariant, ProcessorChain, WriterProfile,
+};
+use std::io::{self, Cursor};
+
+#[test]
+fn explorers_of_sky_layout() {
+    let data = build_archive_with_profile(&[b"hello", b"world!"], GamePreset::ExplorersOfSky.into());
+    let mut expected = vec![
+        0, 0, 0, 0, // the magic
+        2, 0, 0, 0, // the number of file
+        64, 0, 0, 0, 5, 0, 0, 0, // the first file
+        80, 0, 0, 0, 6, 0, 0, 0, // the second file
+        0, 0, 0, 0, 0, 0, 0, 0, // the end of the table of content
+    ];
+    expected.extend_from_slice(&[0xFF; 32]);
+    expected.extend_from_slice(b"hello");
+    expected.extend_from_slice(&[0xFF; 11]);
+    expected.extend_from_slice(b"world!");
+    expected.extend_from_slice(&[0xFF; 10]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn default_layout() {
+    let profile = WriterProfile {
+        padding_byte: 0,
+        variant: HeaderVariant::BigEndian,
+        ..WriterProfile::default()
+    };
+    let data = build_archive_with_profile(&[b"hello", b""], profile);
+    let mut expected = vec![
+        0, 0, 0, 0, // the magic
+        0, 0, 0, 2, // the number of file
+        0, 0, 0, 32, 0, 0, 0, 5, // the first file
+        0, 0, 0, 48, 0, 0, 0, 0, // the second file, empty
+        0, 0, 0, 0, 0, 0, 0, 0, // the end of the table of content
+    ];
+    expected.extend_from_slice(b"hello");
+    expected.extend_from_slice(&[0; 11]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn specific_files_are_aligned_more_strictly() {
+    let profile = WriterProfile::from(Alignment { header: 4, entry: 4 });
+    let mut writer = CPackWriter::new(profile);
+    writer.add(&b"first"[..]).unwrap();
+    writer.add_with_alignment(&b"aligned"[..], 64).unwrap();
+    writer.add(&b"last"[..]).unwrap();
+    let mut data = Vec::new();
+    writer.write(&mut data).unwrap();
+    let pack = CPack::new_from_file(Cursor::new(data)).unwrap();
+    assert_eq!(pack.entry_info(0).unwrap().offset(), 40);
+    assert_eq!(pack.entry_info(1).unwrap().offset(), 64);
+    assert_eq!(pack.entry_info(2).unwrap().offset(), 72);
+    assert_eq!(pack.read_file_to_vec(1).unwrap(), b"aligned");
+}
+
+#[test]
+fn reserved_file_is_written_where_reserved() {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    writer.add(&b"first"[..]).unwrap();
+    let handle = writer.reserve(6);
+    writer.add(&b"last"[..]).unwrap();
+    writer.fill(handle, b"filled".to_vec()).unwrap();
+    let mut data = Vec::new();
+    writer.write(&mut data).unwrap();
+    assert_eq!(data, build_archive_with_profile(&[b"first", b"filled", b"last"], WriterProfile::default()));
+
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    let handle = writer.reserve(6);
+    assert!(writer.fill(handle, b"too long".to_vec()).is_err());
+    assert!(writer.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn layout_past_the_64_bits_limit_is_refused() {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    writer.add(&b"first"[..]).unwrap();
+    writer.add(EntrySource::Reader(Box::new(io::empty()), u64::MAX - 2)).unwrap();
+    let mut data = Vec::new();
+    assert!(matches!(writer.write(&mut data), Err(CPackError::OffsetOverflow(_))));
+    assert!(data.is_empty());
+}
+
+#[test]
+fn processed_reader_shorter_than_its_length_is_refused() {
+    let mut writer = CPackWriter::new(WriterProfile::default());
+    let processors = ProcessorChain::new().then(|_: usize, _: &mut String, _: &mut Vec<u8>| Ok(()));
+    // the length isn't used to preallocate the content
+    writer.add_with_processors(EntrySource::Reader(Box::new(&b"short"[..]), u64::MAX / 2), processors).unwrap();
+    let mut data = Vec::new();
+    assert!(matches!(writer.write(&mut data), Err(CPackError::IOError(err)) if err.kind() == io::ErrorKind::UnexpectedEof));
+    assert!(data.is_empty());
+}